@@ -0,0 +1,121 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    hint::black_box,
+    io::{BufWriter, Write},
+};
+use yumy::{Config, Diagnostic, Label, Source};
+
+/// Returns a source with the given number of lines, all of them the same
+/// small statement.
+fn statements(lines: usize) -> String {
+    "let x = 5;\n".repeat(lines)
+}
+
+/// Compares writing many diagnostics with one shared buffered writer to
+/// creating a buffered writer for each of them, like calling `eprint` in a
+/// loop does.
+fn write_all(c: &mut Criterion) {
+    let src = statements(1000);
+    let source = Source::new(&src, Some("main.rs"));
+    let diagnostics: Vec<_> = (0..1000)
+        .map(|line| Diagnostic::warning(source.clone(), line * 11 + 4..line * 11 + 5, "unused"))
+        .collect();
+
+    let config = Config::default();
+    let mut group = c.benchmark_group("1000 diagnostics");
+    group.bench_function("write_all", |b| {
+        b.iter(|| {
+            let mut writer = BufWriter::new(std::io::sink());
+            yumy::write_all(black_box(&diagnostics), &mut writer, &config).unwrap();
+            writer.flush().unwrap();
+        })
+    });
+
+    group.bench_function("writer per diagnostic", |b| {
+        b.iter(|| {
+            for diagnostic in black_box(&diagnostics) {
+                let mut writer = BufWriter::new(std::io::sink());
+                diagnostic.write_to(&mut writer, &config).unwrap();
+                writer.flush().unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// Compares rendering the same diagnostic 100 times from scratch to
+/// rendering it from a prepared diagnostic.
+fn prepared(c: &mut Criterion) {
+    let src = statements(100);
+    let diagnostic = Diagnostic::new("error: mismatched types")
+        .with_source(Source::new(&src, Some("main.rs")))
+        .with_label(Label::new(4..5, "expected `u32`"))
+        .with_label(Label::new(15..540, "in this block"))
+        .with_label(Label::primary(552..553, "found `&str`"));
+
+    let config = Config::default();
+    let mut group = c.benchmark_group("100 renders");
+    group.bench_function("write_to", |b| {
+        b.iter(|| {
+            for _ in 0..100 {
+                diagnostic.write_to(&mut std::io::sink(), &config).unwrap();
+            }
+        })
+    });
+
+    group.bench_function("prepared", |b| {
+        b.iter(|| {
+            let prepared = black_box(&diagnostic).prepare().unwrap();
+            for _ in 0..100 {
+                prepared.write_to(&mut std::io::sink(), &config).unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// Renders a diagnostic with 50 labels with long messages, which used to be
+/// cloned on every render.
+fn long_labels(c: &mut Criterion) {
+    let src = statements(50);
+    let message = "this value is assigned here but never read afterwards, ".repeat(8);
+    let labels = (0..50)
+        .map(|line| Label::new(line * 11 + 4..line * 11 + 5, &message).with_note(&message))
+        .collect();
+
+    let diagnostic = Diagnostic::new("warning: unused variables")
+        .with_source(Source::new(&src, Some("main.rs")))
+        .with_labels(labels);
+
+    let config = Config::default();
+    c.bench_function("50 long labels", |b| {
+        b.iter(|| {
+            black_box(&diagnostic)
+                .write_to(&mut std::io::sink(), &config)
+                .unwrap();
+        })
+    });
+}
+
+/// Renders a multiline label spanning a big ASCII file, which has every
+/// line measured through the ASCII fast path.
+fn ascii_source(c: &mut Criterion) {
+    let src = "fn add(a: u32, b: u32) -> u32 {\n\ta + b // adds\n}\n".repeat(2000);
+    let diagnostic = Diagnostic::new("error: unterminated file")
+        .with_source(Source::new(&src, Some("main.rs")))
+        .with_label(Label::new(0..src.len() - 1, "in this file"));
+
+    let config = Config::default();
+    c.bench_function("6000 ascii lines", |b| {
+        b.iter(|| {
+            black_box(&diagnostic)
+                .write_to(&mut std::io::sink(), &config)
+                .unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, write_all, prepared, long_labels, ascii_source);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+use yumy::{Config, Diagnostic, Label, Source};
+
+/// Attaches a 100MB file to a diagnostic and renders it with a label on the
+/// first line only.
+fn large_source(c: &mut Criterion) {
+    let src = "let x = 5;\n".repeat(10_000_000);
+    let config = Config::default();
+
+    c.bench_function("first line of 100MB", |b| {
+        b.iter(|| {
+            let source = Source::new(black_box(&src), Some("main.rs"));
+            Diagnostic::warning(source, 4..5, "unused variable")
+                .write_to(&mut std::io::sink(), &config)
+                .unwrap();
+        })
+    });
+}
+
+/// Lays out 10k labels, one in each line of a 10k-line file, which looks
+/// up the lines of each of them.
+fn many_labels(c: &mut Criterion) {
+    let src = "let x = 5;\n".repeat(10_000);
+    let labels = (0..10_000)
+        .map(|line| Label::new(line * 11 + 4..line * 11 + 5, "unused"))
+        .collect();
+
+    let diagnostic = Diagnostic::new("warning: unused variables")
+        .with_source(Source::new(&src, Some("main.rs")))
+        .with_labels(labels);
+
+    c.bench_function("10k labels in 10k lines", |b| {
+        b.iter(|| black_box(black_box(&diagnostic).prepare().unwrap()))
+    });
+}
+
+/// Compares rendering 100 diagnostics that share one source, whose lines
+/// are computed once, to giving each of them a new source.
+fn shared_source(c: &mut Criterion) {
+    let src = "let x = 5;\n".repeat(10_000);
+    let source = Source::new(&src, Some("main.rs"));
+    let config = Config::default();
+
+    let mut group = c.benchmark_group("100 diagnostics");
+    group.bench_function("shared source", |b| {
+        b.iter(|| {
+            for line in 0..100 {
+                let start = line * 11 + 4;
+                Diagnostic::from_source(black_box(&source))
+                    .with_message("warning: unused variable")
+                    .with_label(Label::new(start..start + 1, "never read"))
+                    .write_to(&mut std::io::sink(), &config)
+                    .unwrap();
+            }
+        })
+    });
+
+    group.bench_function("new sources", |b| {
+        b.iter(|| {
+            for line in 0..100 {
+                let start = line * 11 + 4;
+                Diagnostic::from_source(&Source::new(black_box(&src), Some("main.rs")))
+                    .with_message("warning: unused variable")
+                    .with_label(Label::new(start..start + 1, "never read"))
+                    .write_to(&mut std::io::sink(), &config)
+                    .unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, large_source, many_labels, shared_source);
+criterion_main!(benches);
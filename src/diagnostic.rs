@@ -1,31 +1,135 @@
 mod body;
+mod error;
+mod escape;
+mod github;
+mod html;
+mod layout;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod markdown;
+mod measure;
+mod prefix;
+mod prepared;
+mod rendered;
+mod styled;
+mod validation;
 
 /// Module for diagnostic configuration related items.
 pub mod config;
 
-use self::config::Config;
+pub use self::error::DiagnosticError;
+pub use self::layout::{DescriptorView, IndicatorKind, IndicatorLayout, LineLayout};
+pub use self::prefix::LinePrefixWriter;
+pub use self::prepared::PreparedDiagnostic;
+pub use self::rendered::RenderedLine;
+pub use self::validation::ValidationIssue;
+
+use self::config::{Charset, Config, HeaderStyle};
 use super::source::{NoSource, Source, SourceSpan};
-use body::BodyWriter;
-use owo_colors::{OwoColorize, Style};
+use body::{BodyLayout, BodyWriter};
+use owo_colors::Style;
+use prefix::block_indented;
 use std::{
+    borrow::Cow,
+    collections::BTreeMap,
     io::{BufWriter, Write},
     ops::Range,
 };
+use styled::{AnsiWriter, StyledWrite};
+
+/// The severity of a [`Diagnostic`]. When set, it's shown before the message
+/// of the diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// A note, giving information that isn't a problem.
+    Note,
+    /// A warning, pointing out a possible problem.
+    Warning,
+    /// An error, pointing out a problem.
+    Error,
+}
+
+impl Severity {
+    /// The name of this severity, as shown before the message.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// Returns the style used to render this severity.
+    fn style(&self, config: &Config) -> Style {
+        match self {
+            Severity::Note => config.styles.note,
+            Severity::Warning => config.styles.warning,
+            Severity::Error => config.styles.error,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 /// A label is a message that points to a specific
 /// part of the source of a [`Diagnostic`].
+///
+/// Where the indicators of multiline labels are placed depends only on their
+/// spans, not on the order the labels were added in.
+///
+/// Multiline labels only take as many columns as there are labels active at
+/// the same time, and labels that end on a line free their column for the
+/// ones that start on it.
+///
+/// New fields may be added to labels, so they can't be built with struct
+/// literals: use [`Label::new`] and friends instead.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Label {
-    /// The message of this label.
+    /// The message of this label. If it has multiple lines, they are all
+    /// aligned with the first one.
     pub message: String,
     /// The span this label refers to.
     pub span: SourceSpan,
     /// The indicator style of this label.
     pub indicator_style: Option<Style>,
+    /// Whether this is the primary label of its diagnostic.
+    pub primary: bool,
+    /// The order in which the message of this label is shown among the
+    /// others on the same line, from lowest to highest. Labels without an
+    /// order are treated as having an order of 0, and labels with the same
+    /// order are shown from the widest to the narrowest.
+    pub order: Option<i32>,
+    /// A note elaborating on the message of this label, shown below it.
+    pub note: Option<String>,
+    /// Other spans this label refers to, which get indicators of their own
+    /// but share its message. Useful when a single error spans disjoint
+    /// parts of the source, such as mismatched delimiters.
+    pub extra_spans: Vec<SourceSpan>,
 }
 
 impl Label {
     /// Creates a new label.
+    ///
+    /// A label with an empty span is shown as a single caret right where the
+    /// span is, which is useful to point at something missing, or at an
+    /// empty line:
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: expected an item")
+    ///     .with_source(Source::new("\n\n\n", Some("main.rs")))
+    ///     .with_label(Label::new(1..1, "expected an item here"));
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains("2 │  \n  :  ^ expected an item here\n"));
+    /// ```
     pub fn new<S, M>(span: S, message: M) -> Self
     where
         S: Into<SourceSpan>,
@@ -35,10 +139,14 @@ impl Label {
             message: message.to_string(),
             span: span.into(),
             indicator_style: None,
+            primary: false,
+            order: None,
+            note: None,
+            extra_spans: Vec::new(),
         }
     }
 
-    /// Creates a new label with the given style for it's indicator.
+    /// Creates a new label with the given style for its indicator.
     pub fn styled<S, M>(span: S, message: M, style: Style) -> Self
     where
         S: Into<SourceSpan>,
@@ -48,22 +156,89 @@ impl Label {
             message: message.to_string(),
             span: span.into(),
             indicator_style: Some(style),
+            primary: false,
+            order: None,
+            note: None,
+            extra_spans: Vec::new(),
         }
     }
 
+    /// Creates a new primary label. A primary label points to the main
+    /// location of a diagnostic and is rendered with a distinct style.
+    pub fn primary<S, M>(span: S, message: M) -> Self
+    where
+        S: Into<SourceSpan>,
+        M: ToString,
+    {
+        Self {
+            message: message.to_string(),
+            span: span.into(),
+            indicator_style: None,
+            primary: true,
+            order: None,
+            note: None,
+            extra_spans: Vec::new(),
+        }
+    }
+
+    /// Sets the order in which the message of this label is shown among the
+    /// others on the same line. See [`Label::order`].
+    #[inline(always)]
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Sets a note elaborating on the message of this label, which is shown
+    /// below it.
+    #[inline(always)]
+    pub fn with_note<N>(mut self, note: N) -> Self
+    where
+        N: ToString,
+    {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    /// Adds another span to this label, which is shown with an indicator of
+    /// its own. The message is shown only once, next to the indicator of
+    /// [`Label::span`], and spans in the same line share a single row.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: mismatched delimiters")
+    ///     .with_source(Source::new("let v = (1, 2];", None))
+    ///     .with_label(Label::new(8..9, "these don't match").with_extra_span(13..14));
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// assert!(rendered.contains(
+    ///     "1 │  let v = (1, 2];\n  \
+    ///      :          ^    ^ these don't match\n"
+    /// ));
+    /// ```
+    #[inline(always)]
+    pub fn with_extra_span<S>(mut self, span: S) -> Self
+    where
+        S: Into<SourceSpan>,
+    {
+        self.extra_spans.push(span.into());
+        self
+    }
+
+    /// Returns all of the spans of this label: [`Label::span`] followed by
+    /// the [extra ones](Label::extra_spans).
+    pub fn spans(&self) -> impl Iterator<Item = SourceSpan> + '_ {
+        std::iter::once(self.span).chain(self.extra_spans.iter().copied())
+    }
+
     /// Returns the line range of this label in the given source.
     ///
     /// # Panics
     /// Panics if the span is out of bounds.
     fn line_range(&self, src: &Source) -> Range<u32> {
-        let start = src
-            .line_index_of_byte(self.span.start())
-            .expect("span start should be in bounds");
-        let end = src
-            .line_index_of_byte(self.span.end().saturating_sub(1))
-            .expect("span end should be in bounds");
-
-        start..(end + 1)
+        src.line_range_of_span(self.span)
+            .expect("span should be in bounds")
     }
 
     /// Returns whether this label is singleline or not.
@@ -76,12 +251,191 @@ impl Label {
     }
 }
 
+/// The kind of a [`Footnote`]. Each kind has an indicator of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FootnoteKind {
+    /// A note, giving more context.
+    #[default]
+    Note,
+    /// A help message, suggesting a fix.
+    Help,
+    /// Some additional information.
+    Info,
+}
+
+/// A footnote is a message shown after the body of a [`Diagnostic`].
+///
+/// The indicator of a footnote is aligned with the vertical bar of the body,
+/// however wide the line numbers are.
+#[derive(Debug, Clone)]
+pub struct Footnote {
+    /// The kind of this footnote.
+    pub kind: FootnoteKind,
+    /// The message of this footnote. If it has multiple lines, they are all
+    /// aligned with the first one.
+    pub message: String,
+    /// The index of the label this footnote refers to, if any.
+    pub label: Option<usize>,
+}
+
+impl Footnote {
+    /// Creates a new footnote of the given kind.
+    pub fn of_kind<M>(kind: FootnoteKind, message: M) -> Self
+    where
+        M: ToString,
+    {
+        Self {
+            kind,
+            message: message.to_string(),
+            label: None,
+        }
+    }
+
+    /// Makes this footnote refer to the label with the given index. The
+    /// reference is only shown when [`Config::number_labels`] is enabled,
+    /// before the message of the footnote.
+    #[inline(always)]
+    pub fn referencing(mut self, label: usize) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Creates a new note footnote. Same as [`Footnote::note`].
+    #[inline(always)]
+    pub fn new<M>(message: M) -> Self
+    where
+        M: ToString,
+    {
+        Self::note(message)
+    }
+
+    /// Creates a new note footnote.
+    #[inline(always)]
+    pub fn note<M>(message: M) -> Self
+    where
+        M: ToString,
+    {
+        Self::of_kind(FootnoteKind::Note, message)
+    }
+
+    /// Creates a new help footnote.
+    #[inline(always)]
+    pub fn help<M>(message: M) -> Self
+    where
+        M: ToString,
+    {
+        Self::of_kind(FootnoteKind::Help, message)
+    }
+
+    /// Creates a new info footnote.
+    #[inline(always)]
+    pub fn info<M>(message: M) -> Self
+    where
+        M: ToString,
+    {
+        Self::of_kind(FootnoteKind::Info, message)
+    }
+}
+
+impl Footnote {
+    /// Returns the indicator character and style of this footnote.
+    fn indicator(&self, config: &Config) -> (char, Style) {
+        match self.kind {
+            FootnoteKind::Note => (
+                config.charset.note_indicator,
+                config.styles.footnote_indicator,
+            ),
+            FootnoteKind::Help => (config.charset.help_indicator, config.styles.help_indicator),
+            FootnoteKind::Info => (config.charset.info_indicator, config.styles.info_indicator),
+        }
+    }
+
+    /// Writes the message of this footnote, preceded by a reference to the
+    /// label with the given index if any. Lines after the first are indented
+    /// by `indent` so that they're aligned with it.
+    fn write_message<W>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        reference: Option<usize>,
+        indent: usize,
+    ) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        let message = config.transform_message(&self.message);
+        if let Some(label) = reference {
+            writer.write_styled(
+                format_args!(
+                    "{}: {} ({})",
+                    config.localization.footnote_kind(self.kind),
+                    config.localization.see,
+                    label + 1
+                ),
+                config.styles.left_column,
+            )?;
+
+            if !message.is_empty() {
+                write!(writer, ": ")?;
+            }
+        }
+
+        let mut lines = message.lines();
+        write!(writer, "{}", lines.next().unwrap_or_default())?;
+        for line in lines {
+            write!(writer, "\n{:indent$}{}", "", line)?;
+        }
+
+        writeln!(writer)
+    }
+}
+
+impl<T> From<T> for Footnote
+where
+    T: std::fmt::Display,
+{
+    #[inline(always)]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Adapter that allows writing to a [`std::fmt::Write`]r through [`Write`].
+/// Diagnostics are always written as whole `str`s, so the bytes received are
+/// always valid UTF-8.
+struct FmtWriter<'a, W>(&'a mut W);
+
+impl<W> Write for FmtWriter<'_, W>
+where
+    W: std::fmt::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = std::str::from_utf8(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        self.0.write_str(text).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 /// A diagnostic.
+///
+/// Footnotes are rendered in the order they're stored in, which is the order
+/// they were added in unless [`Diagnostic::with_footnote_at`] is used.
 #[derive(Debug, Clone)]
 pub struct Diagnostic<Src> {
     message: String,
+    severity: Option<Severity>,
+    code: Option<String>,
+    code_url: Option<String>,
+    charset: Option<Charset>,
+    metadata: BTreeMap<String, String>,
     labels: Vec<Label>,
-    footnotes: Vec<String>,
+    footnotes: Vec<Footnote>,
     source: Src,
 }
 
@@ -94,6 +448,11 @@ impl Diagnostic<NoSource> {
     {
         Self {
             message: message.to_string(),
+            severity: None,
+            code: None,
+            code_url: None,
+            charset: None,
+            metadata: BTreeMap::new(),
             labels: Vec::new(),
             footnotes: Vec::new(),
             source: NoSource,
@@ -105,6 +464,11 @@ impl Diagnostic<NoSource> {
     pub fn with_source(self, source: Source<'_>) -> Diagnostic<Source<'_>> {
         Diagnostic {
             message: self.message,
+            severity: self.severity,
+            code: self.code,
+            code_url: self.code_url,
+            charset: self.charset,
+            metadata: self.metadata,
             labels: self.labels,
             footnotes: self.footnotes,
             source,
@@ -113,7 +477,163 @@ impl Diagnostic<NoSource> {
 }
 
 impl<Src> Diagnostic<Src> {
-    /// Add a [`Label`] to this diagnostic.
+    /// Returns whether this diagnostic has no message, no labels and no
+    /// footnotes, in which case it would render as an empty block.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.message.is_empty() && self.labels.is_empty() && self.footnotes.is_empty()
+    }
+
+    /// Returns the message of this diagnostic.
+    #[inline(always)]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the labels of this diagnostic.
+    #[inline(always)]
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    /// Returns the source of this diagnostic.
+    #[inline(always)]
+    pub fn source(&self) -> &Src {
+        &self.source
+    }
+
+    /// Returns the severity of this diagnostic, if any.
+    #[inline(always)]
+    pub fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    /// Sets the severity of this diagnostic, which is shown before its
+    /// message.
+    #[inline(always)]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Returns the code of this diagnostic, if any.
+    #[inline(always)]
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Sets the code of this diagnostic, such as `E0425`, which is shown
+    /// before its message.
+    #[inline(always)]
+    pub fn with_code<C>(mut self, code: C) -> Self
+    where
+        C: ToString,
+    {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Returns the URL of the documentation of the code of this diagnostic,
+    /// if any.
+    #[inline(always)]
+    pub fn code_url(&self) -> Option<&str> {
+        self.code_url.as_deref()
+    }
+
+    /// Sets the URL of the documentation of the code of this diagnostic.
+    /// When hyperlinks are enabled (see [`Config::hyperlinks`]), the code is
+    /// rendered as a link to it.
+    /// Control characters in the URL are percent-encoded, so that they can't
+    /// end the link early.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, HyperlinkChoice, Severity, Source};
+    /// let diagnostic = Diagnostic::new("cannot find value `y` in this scope")
+    ///     .with_severity(Severity::Error)
+    ///     .with_code("E0425")
+    ///     .with_code_url("https://example.com/E0425")
+    ///     .with_source(Source::new("let x = y;", None));
+    ///
+    /// let config = Config::default().with_hyperlinks(HyperlinkChoice::Always);
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// let start = rendered.find("\x1b]8;;https://example.com/E0425\x1b\\").unwrap();
+    /// let end = rendered.find("\x1b]8;;\x1b\\").unwrap();
+    /// assert!(rendered[start..end].contains("E0425"));
+    ///
+    /// let config = config.with_color(ColorChoice::Never);
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.starts_with("error[E0425]: cannot find value"));
+    ///
+    /// let diagnostic = diagnostic.with_code_url("https://example.com/\x1b]8;;evil\x07");
+    /// let config = Config::default().with_hyperlinks(HyperlinkChoice::Always);
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains("\x1b]8;;https://example.com/%1B]8;;evil%07\x1b\\"));
+    /// ```
+    #[inline(always)]
+    pub fn with_code_url<U>(mut self, url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.code_url = Some(url.to_string());
+        self
+    }
+
+    /// Sets the charset used to render this diagnostic, which takes
+    /// precedence over the one of the [`Config`] it's rendered with.
+    ///
+    /// ```
+    /// # use yumy::{Charset, ColorChoice, Config, Diagnostic, Source};
+    /// let source = Source::new("let x = 5;", None);
+    /// let diagnostic = Diagnostic::warning(source, 4..9, "unused assignment")
+    ///     .with_charset_override(Charset::squiggly());
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains(":      ^~~~~\n"));
+    /// ```
+    #[inline(always)]
+    pub fn with_charset_override(mut self, charset: Charset) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    /// Returns the metadata attached to this diagnostic.
+    #[inline(always)]
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Attaches a key/value pair of metadata to this diagnostic, replacing
+    /// any previous value for the same key.
+    ///
+    /// Metadata is meant for integrations with external systems, such as
+    /// alternative emitters, and is ignored by the terminal renderer.
+    ///
+    /// ```
+    /// # use yumy::Diagnostic;
+    /// let diagnostic = Diagnostic::new("error: unused import")
+    ///     .with_meta("rule", "unused-import")
+    ///     .with_meta("docs", "https://example.com/unused-import");
+    ///
+    /// let metadata = diagnostic.metadata();
+    /// assert_eq!(metadata.get("rule").map(String::as_str), Some("unused-import"));
+    /// assert_eq!(metadata.len(), 2);
+    /// ```
+    #[inline(always)]
+    pub fn with_meta<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: ToString,
+        V: ToString,
+    {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the message of this diagnostic.
     #[inline(always)]
     pub fn with_message<M>(mut self, message: M) -> Self
     where
@@ -136,6 +656,35 @@ impl<Src> Diagnostic<Src> {
         self
     }
 
+    /// Returns the primary [`Label`] of this diagnostic. If no label is marked
+    /// as primary, the one that starts the earliest is returned.
+    pub fn primary_label(&self) -> Option<&Label> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or_else(|| self.labels.iter().min_by_key(|label| label.span.start()))
+    }
+
+    /// Returns the [`Label`]s of this diagnostic whose span contains the given
+    /// byte.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Label, SourceSpan};
+    /// let diagnostic = Diagnostic::new("mismatched types")
+    ///     .with_label(Label::new(SourceSpan::new(0, 10), "this expression"))
+    ///     .with_label(Label::new(SourceSpan::new(4, 6), "this operand"))
+    ///     .with_label(Label::new(SourceSpan::new(12, 16), "this type"));
+    ///
+    /// let messages: Vec<_> = diagnostic.labels_at(5).map(|l| &l.message).collect();
+    /// assert_eq!(messages, ["this expression", "this operand"]);
+    /// assert_eq!(diagnostic.labels_at(11).count(), 0);
+    /// ```
+    pub fn labels_at(&self, byte: u32) -> impl Iterator<Item = &Label> {
+        self.labels
+            .iter()
+            .filter(move |label| label.spans().any(|span| span.contains(byte)))
+    }
+
     /// Replaces the [`Label`]s of this diagnostic.
     #[inline(always)]
     pub fn with_labels(mut self, labels: Vec<Label>) -> Self {
@@ -143,100 +692,513 @@ impl<Src> Diagnostic<Src> {
         self
     }
 
-    /// Add a footnote to this diagnostic. A footnote is a message
-    /// shown after the body of a diagnostic.
+    /// Add multiple [`Label`]s to this diagnostic, after the ones it already
+    /// has.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Label};
+    /// let mut diagnostic = Diagnostic::new("error: unknown variables")
+    ///     .with_label(Label::new(8..9, "first"));
+    /// diagnostic.extend_labels([Label::new(12..13, "second"), Label::new(16..17, "third")]);
+    ///
+    /// let messages: Vec<_> = diagnostic.labels().iter().map(|l| &l.message).collect();
+    /// assert_eq!(messages, ["first", "second", "third"]);
+    /// ```
+    #[inline(always)]
+    pub fn extend_labels<I>(&mut self, labels: I)
+    where
+        I: IntoIterator<Item = Label>,
+    {
+        self.labels.extend(labels);
+    }
+
+    /// Add a [`Footnote`] to this diagnostic. Plain messages are
+    /// turned into notes.
     #[inline(always)]
     pub fn add_footnote<F>(&mut self, footnote: F)
     where
-        F: ToString,
+        F: Into<Footnote>,
     {
-        self.footnotes.push(footnote.to_string());
+        self.footnotes.push(footnote.into());
     }
 
-    /// Add a footnote to this diagnostic. A footnote is a message
-    /// shown after the body of a diagnostic.
+    /// Add a [`Footnote`] to this diagnostic. Plain messages are
+    /// turned into notes.
     #[inline(always)]
     pub fn with_footnote<F>(mut self, footnote: F) -> Self
     where
-        F: ToString,
+        F: Into<Footnote>,
+    {
+        self.add_footnote(footnote);
+        self
+    }
+
+    /// Insert a [`Footnote`] at the given position among the footnotes of
+    /// this diagnostic. Plain messages are turned into notes.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of footnotes.
+    #[inline(always)]
+    pub fn insert_footnote<F>(&mut self, index: usize, footnote: F)
+    where
+        F: Into<Footnote>,
+    {
+        self.footnotes.insert(index, footnote.into());
+    }
+
+    /// Insert a [`Footnote`] at the given position among the footnotes of
+    /// this diagnostic. Plain messages are turned into notes.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Footnote, Source};
+    /// let diagnostic = Diagnostic::new("error: build failed")
+    ///     .with_source(Source::new("", None))
+    ///     .with_note("while compiling `main`")
+    ///     .with_footnote_at(0, Footnote::info("2 errors found"));
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    ///
+    /// let summary = rendered.find("2 errors found").unwrap();
+    /// let note = rendered.find("while compiling").unwrap();
+    /// assert!(summary < note);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of footnotes.
+    #[inline(always)]
+    pub fn with_footnote_at<F>(mut self, index: usize, footnote: F) -> Self
+    where
+        F: Into<Footnote>,
     {
-        self.add_footnote(footnote.to_string());
+        self.insert_footnote(index, footnote);
         self
     }
+
+    /// Add a note footnote to this diagnostic.
+    #[inline(always)]
+    pub fn with_note<M>(self, message: M) -> Self
+    where
+        M: ToString,
+    {
+        self.with_footnote(Footnote::new(message))
+    }
+
+    /// Add a help footnote to this diagnostic.
+    #[inline(always)]
+    pub fn with_help<M>(self, message: M) -> Self
+    where
+        M: ToString,
+    {
+        self.with_footnote(Footnote::help(message))
+    }
 }
 
 impl<'src> Diagnostic<Source<'src>> {
-    /// Calculates the left padding necessary for this diagnostic.
+    /// Creates a new diagnostic with an empty message for the given source,
+    /// which is cloned. Since clones of a source share its lines, this is
+    /// cheap even for big sources, and the lines are only computed once no
+    /// matter how many diagnostics use them.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let src = "let x = 5;\n".repeat(1000);
+    /// let source = Source::new(&src, Some("main.rs"));
+    ///
+    /// let diagnostics: Vec<_> = (0..100)
+    ///     .map(|line: usize| {
+    ///         let start = line * 11 + 4;
+    ///         Diagnostic::from_source(&source)
+    ///             .with_message("warning: unused variable")
+    ///             .with_label(Label::new(start..start + 1, "never read"))
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut rendered = String::new();
+    /// for diagnostic in &diagnostics {
+    ///     diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// }
+    ///
+    /// assert!(rendered.contains("100 │  let x = 5;"));
+    ///
+    /// // the lines of the source were computed once, and are shared by all
+    /// assert!(diagnostics
+    ///     .iter()
+    ///     .all(|diagnostic| diagnostic.source().shares_lines_with(&source)));
+    /// ```
+    #[inline]
+    pub fn from_source(source: &Source<'src>) -> Self {
+        Diagnostic::new("").with_source(source.clone())
+    }
+
+    /// Creates a new diagnostic with the given severity, pointing to a
+    /// single span of the given source with a primary label.
+    fn with_severity_at<S, M>(severity: Severity, source: Source<'src>, span: S, message: M) -> Self
+    where
+        S: Into<SourceSpan>,
+        M: ToString,
+    {
+        Diagnostic::new(message)
+            .with_severity(severity)
+            .with_source(source)
+            .with_label(Label::primary(span, ""))
+    }
+
+    /// Creates a new error pointing to a single span of the given source.
+    ///
+    /// This is a shorthand for the common case of a diagnostic with a single
+    /// label. When more control is needed, build the diagnostic with
+    /// [`Diagnostic::new`] instead:
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Severity, Source, SourceSpan};
+    /// let source = Source::new("let x = y;", Some("main.rs"));
+    /// let short = Diagnostic::error(source.clone(), SourceSpan::new(8, 9), "unknown variable");
+    /// let long = Diagnostic::new("unknown variable")
+    ///     .with_severity(Severity::Error)
+    ///     .with_source(source)
+    ///     .with_label(Label::primary(SourceSpan::new(8, 9), ""));
+    ///
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// let (mut a, mut b) = (String::new(), String::new());
+    /// short.write_to_fmt(&mut a, &config).unwrap();
+    /// long.write_to_fmt(&mut b, &config).unwrap();
+    /// assert_eq!(a, b);
+    /// assert!(a.starts_with("error: unknown variable\n"));
+    /// ```
+    #[inline]
+    pub fn error<S, M>(source: Source<'src>, span: S, message: M) -> Self
+    where
+        S: Into<SourceSpan>,
+        M: ToString,
+    {
+        Self::with_severity_at(Severity::Error, source, span, message)
+    }
+
+    /// Creates a new warning pointing to a single span of the given source.
+    /// See [`Diagnostic::error`].
+    #[inline]
+    pub fn warning<S, M>(source: Source<'src>, span: S, message: M) -> Self
+    where
+        S: Into<SourceSpan>,
+        M: ToString,
+    {
+        Self::with_severity_at(Severity::Warning, source, span, message)
+    }
+
+    /// Creates a new note pointing to a single span of the given source.
+    /// See [`Diagnostic::error`].
+    #[inline]
+    pub fn note<S, M>(source: Source<'src>, span: S, message: M) -> Self
+    where
+        S: Into<SourceSpan>,
+        M: ToString,
+    {
+        Self::with_severity_at(Severity::Note, source, span, message)
+    }
+
+    /// Merges another diagnostic into this one, so that both are rendered as
+    /// a single block. Returns the merged diagnostic, or an error with both
+    /// diagnostics unchanged if their sources differ, since the labels of
+    /// `other` can't be rendered against the source of this one.
+    ///
+    /// The message, severity, code and source of this diagnostic are kept,
+    /// while the message of `other` is added as a [`FootnoteKind::Note`]
+    /// footnote, preceded by its severity and code and followed by its code
+    /// URL, if it has them. The labels and footnotes of `other` are appended
+    /// after the ones of this diagnostic, with the labels that footnotes refer
+    /// to renumbered accordingly. Metadata of `other` is only added for keys
+    /// this diagnostic doesn't already have.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Footnote, Source};
+    /// let source = Source::new("let x = a + b;", Some("main.rs"));
+    /// let first = Diagnostic::error(source.clone(), 8..9, "unknown variable `a`");
+    /// let second = Diagnostic::error(source, 12..13, "unknown variable `b`")
+    ///     .with_code("E0425")
+    ///     .with_footnote(Footnote::new("declare it first").referencing(0));
+    ///
+    /// let merged = first.merge(second).unwrap();
+    /// assert_eq!(merged.message(), "unknown variable `a`");
+    /// assert_eq!(merged.code(), None);
+    /// assert_eq!(merged.labels().len(), 2);
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::plain().with_number_labels(true);
+    /// merged.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.ends_with(
+    ///     "  > error[E0425]: unknown variable `b`\n  \
+    ///      > note: see (2): declare it first\n\
+    ///      \n"
+    /// ));
+    ///
+    /// let other = Diagnostic::error(Source::new("b", Some("b.rs")), 0..1, "unused");
+    /// let (merged, other) = *merged.merge(other).unwrap_err();
+    /// assert_eq!(merged.labels().len(), 2);
+    /// assert_eq!(other.message(), "unused");
+    /// ```
+    pub fn merge(mut self, other: Self) -> Result<Self, Box<(Self, Self)>> {
+        if self.source != other.source {
+            return Err(Box::new((self, other)));
+        }
+
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+
+        let label_offset = self.labels.len();
+        self.labels.extend(other.labels);
+
+        let mut note = String::new();
+        if let Some(severity) = other.severity {
+            note.push_str(severity.as_str());
+        }
+
+        if let Some(code) = &other.code {
+            note.push_str(&format!("[{code}]"));
+        }
+
+        if !note.is_empty() {
+            note.push_str(": ");
+        }
+
+        note.push_str(&other.message);
+        if let Some(url) = &other.code_url {
+            note.push_str(&format!(" ({url})"));
+        }
+
+        if !note.is_empty() {
+            self.footnotes.push(Footnote::new(note));
+        }
+
+        self.footnotes
+            .extend(other.footnotes.into_iter().map(|mut footnote| {
+                footnote.label = footnote.label.map(|label| label + label_offset);
+                footnote
+            }));
+
+        Ok(self)
+    }
+
+    /// Returns the config to render this diagnostic with: the given one with
+    /// its colors resolved (see [`Config::resolve_colors`]) and the charset
+    /// override of this diagnostic applied, if any.
+    fn resolve_config<'c>(&self, config: &'c Config, colored_by_default: bool) -> Cow<'c, Config> {
+        let mut config = config.resolve_colors(colored_by_default);
+        if let Some(charset) = &self.charset {
+            config.to_mut().charset = charset.clone();
+        }
+
+        config
+    }
+
+    /// Checks that the spans of all labels of this diagnostic are within the
+    /// bounds of its source, returning an [`InvalidInput`] error otherwise.
+    ///
+    /// If [`Config::clamp_spans`] is enabled, a copy of this diagnostic with
+    /// the spans clamped to the source is returned instead of the error.
+    ///
+    /// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
+    fn check_bounds(&self, config: &Config) -> std::io::Result<Option<Self>> {
+        match self.ensure_in_bounds() {
+            Ok(()) => return Ok(None),
+            Err(e) if !config.clamp_spans => return Err(e),
+            Err(_) => (),
+        }
+
+        let len = self.source.src().len() as u32;
+        let mut clamped = self.clone();
+        for label in &mut clamped.labels {
+            for span in std::iter::once(&mut label.span).chain(&mut label.extra_spans) {
+                let end = span.end().min(len);
+                *span = SourceSpan::new(span.start().min(end), end);
+            }
+        }
+
+        Ok(Some(clamped))
+    }
+
+    /// Checks that the spans of all labels of this diagnostic are within the
+    /// bounds of its source, returning an [`InvalidInput`] error otherwise.
+    ///
+    /// [`InvalidInput`]: std::io::ErrorKind::InvalidInput
+    fn ensure_in_bounds(&self) -> std::io::Result<()> {
+        let len = self.source.src().len() as u32;
+        let Some(index) = self
+            .labels
+            .iter()
+            .position(|label| label.spans().any(|span| span.end() > len))
+        else {
+            return Ok(());
+        };
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("span of label {index} is out of the bounds of the source"),
+        ))
+    }
+
+    /// Calculates the left padding necessary for this diagnostic. This is the
+    /// number of digits of the biggest line number shown, and is at least 1
+    /// so that the header and footnotes stay aligned even without labels.
     fn left_padding(&self) -> usize {
-        let mut padding = 0;
-        for label in &self.labels {
-            // find last line of label
-            let line_index = self
-                .source
-                .line_index_of_byte(label.span.end().saturating_sub(1));
-            let index_algs = line_index
-                .map(|x| f32::log10(x as f32).floor() as usize)
-                .unwrap_or(0);
-            if index_algs > padding {
-                padding = index_algs;
+        self.left_padding_of(&self.labels)
+    }
+
+    /// Calculates the left padding necessary to show the given labels of
+    /// this diagnostic. See [`Diagnostic::left_padding`].
+    fn left_padding_of<'l, I>(&self, labels: I) -> usize
+    where
+        I: IntoIterator<Item = &'l Label>,
+    {
+        labels
+            .into_iter()
+            .flat_map(Label::spans)
+            .filter_map(|span| {
+                // find last line of span
+                self.source.line_index_of_byte(span.end().saturating_sub(1))
+            })
+            .map(|line_index| self.source.line_number(line_index).ilog10() as usize + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Writes the message of this diagnostic, preceded by its severity and
+    /// code if it has them.
+    fn write_message<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        let style = self
+            .severity
+            .map_or(config.styles.left_column, |severity| severity.style(config));
+
+        if let Some(severity) = self.severity {
+            writer.write_styled(config.localization.severity(severity), style)?;
+        }
+
+        if let Some(code) = &self.code {
+            writer.write_styled('[', style)?;
+            match self.code_url.as_deref().filter(|_| config.use_hyperlinks()) {
+                Some(url) => writer.write_link(url, code, style)?,
+                None => writer.write_styled(code, style)?,
             }
+            writer.write_styled(']', style)?;
         }
 
-        padding + 1
+        if self.severity.is_some() || self.code.is_some() {
+            write!(writer, ": ")?;
+        }
+
+        write!(writer, "{}", self.message)
     }
 
-    fn write_header<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    /// Writes the name of the source of this diagnostic, as a hyperlink to
+    /// the line of the primary label in its file if possible.
+    fn write_source_name<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
+    {
+        let name = self.source.name().unwrap_or(&config.unknown_source_name);
+        let style = config.styles.source_name;
+
+        let line = self
+            .primary_label()
+            .and_then(|label| {
+                self.source
+                    .position_at_with(label.span.start(), config.width_options())
+            })
+            .map(|(line, _)| line);
+
+        match self
+            .source
+            .file_url(line)
+            .filter(|_| config.use_hyperlinks())
+        {
+            Some(url) => writer.write_link(&url, name, style),
+            None => writer.write_styled(name, style),
+        }
+    }
+
+    fn write_header<W>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        left_padding: usize,
+    ) -> std::io::Result<()>
+    where
+        W: StyledWrite,
     {
-        writeln!(writer, "{}", self.message)?;
+        self.write_message(writer, config)?;
+        writeln!(writer)?;
 
-        let left_padding = self.left_padding();
+        let style = config.styles.left_column;
         write!(writer, "{:padding$}", "", padding = left_padding)?;
-        writeln!(
-            writer,
-            " {} {}{}{}",
-            '@'.style(config.styles.left_column),
-            '['.style(config.styles.left_column),
-            self.source
-                .name()
-                .unwrap_or("unknown")
-                .style(config.styles.source_name),
-            ']'.style(config.styles.left_column)
-        )?;
-        Ok(())
+        match config.header_style {
+            HeaderStyle::Bracketed => {
+                write!(writer, " ")?;
+                writer.write_styled('@', style)?;
+                write!(writer, " ")?;
+                writer.write_styled('[', style)?;
+            }
+            HeaderStyle::Arrow => {
+                writer.write_styled("-->", style)?;
+                write!(writer, " ")?;
+            }
+        }
+
+        self.write_source_name(writer, config)?;
+
+        let position = self
+            .primary_label()
+            .filter(|_| config.show_position)
+            .and_then(|label| {
+                self.source
+                    .position_at_with(label.span.start(), config.width_options())
+            });
+
+        if let Some((line, column)) = position {
+            writer.write_styled(format_args!(":{line}:{column}"), config.styles.source_name)?;
+        }
+
+        if config.header_style == HeaderStyle::Bracketed {
+            writer.write_styled(']', style)?;
+        }
+
+        writeln!(writer)
     }
 
     fn write_header_compact<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
     {
-        writeln!(writer, "{}", self.message)?;
-        writeln!(
-            writer,
-            "{} {}{}{}",
-            '@'.style(config.styles.left_column),
-            '['.style(config.styles.left_column),
-            self.source
-                .name()
-                .unwrap_or("unknown")
-                .style(config.styles.source_name),
-            "]:".style(config.styles.left_column)
-        )?;
-        Ok(())
+        let style = config.styles.left_column;
+        self.write_message(writer, config)?;
+        writeln!(writer)?;
+        writer.write_styled('@', style)?;
+        write!(writer, " ")?;
+        writer.write_styled('[', style)?;
+        self.write_source_name(writer, config)?;
+        writer.write_styled("]:", style)?;
+        writeln!(writer)
     }
 
-    fn write_body<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    fn write_body<W>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        left_padding: usize,
+        layout: &BodyLayout<'_>,
+    ) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
     {
-        let body_lines = BodyWriter::new(
-            writer,
-            self.source.clone(),
-            config,
-            self.left_padding(),
-            self.labels.as_slice(),
-        );
+        if layout.is_empty() {
+            return Ok(());
+        }
+
+        let body_lines = BodyWriter::new(writer, &self.source, config, left_padding, layout);
 
         body_lines.write()?;
 
@@ -245,53 +1207,79 @@ impl<'src> Diagnostic<Source<'src>> {
 
     fn write_body_compact<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
     {
-        for label in &self.labels {
+        let (left_column, source) = (config.styles.left_column, config.styles.source);
+        for (ordinal, label) in self.labels.iter().enumerate() {
+            writer.write_styled(config.charset.vertical_bar, left_column)?;
+            write!(writer, " ")?;
+            writer.write_styled('[', left_column)?;
+
             let range = label.line_range(&self.source);
             if range.start + 1 == range.end {
-                writeln!(
-                    writer,
-                    "{} {}{}{}{}{}",
-                    config.charset.vertical_bar.style(config.styles.left_column),
-                    '['.style(config.styles.left_column),
-                    "line ".style(config.styles.source),
-                    range.start.style(config.styles.source),
-                    "]: ".style(config.styles.left_column),
-                    label.message
-                )?;
+                writer.write_styled(&config.localization.line, source)?;
+                write!(writer, " ")?;
+                writer.write_styled(self.source.line_number(range.start), source)?;
             } else {
-                writeln!(
-                    writer,
-                    "{} {}{}{:?}{}{}",
-                    config.charset.vertical_bar.style(config.styles.left_column),
-                    '['.style(config.styles.left_column),
-                    "lines ".style(config.styles.source),
-                    range.style(config.styles.source),
-                    "]: ".style(config.styles.left_column),
-                    label.message
+                writer.write_styled(&config.localization.lines, source)?;
+                write!(writer, " ")?;
+                writer.write_styled(
+                    format_args!(
+                        "{:?}",
+                        self.source.line_number(range.start)
+                            ..=self.source.line_number(range.end - 1)
+                    ),
+                    source,
                 )?;
             }
+
+            writer.write_styled("]: ", left_column)?;
+            if config.number_labels {
+                writer.write_styled(format_args!("({})", ordinal + 1), left_column)?;
+                write!(writer, " ")?;
+            }
+
+            writeln!(writer, "{}", config.transform_message(&label.message))?;
         }
 
         Ok(())
     }
 
-    fn write_footnotes<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    /// Returns the index of the label the given footnote references, if it
+    /// should be shown. References to labels that don't exist are never
+    /// shown (see [`ValidationIssue::FootnoteReferenceOutOfRange`]).
+    fn footnote_reference(&self, footnote: &Footnote, config: &Config) -> Option<usize> {
+        footnote
+            .label
+            .filter(|&label| config.number_labels && label < self.labels.len())
+    }
+
+    fn write_footnotes<W>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        left_padding: usize,
+    ) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
     {
-        let left_padding = self.left_padding();
-
         for footnote in &self.footnotes {
-            write!(
+            // the indicator goes right under the vertical bar of the body,
+            // which comes after the line numbers and a space
+            let (indicator, style) = footnote.indicator(config);
+            write!(writer, "{:padding$} ", "", padding = left_padding)?;
+            writer.write_styled(indicator, style)?;
+            write!(writer, " ")?;
+
+            let indicator_width = config
+                .width_options()
+                .display_width(indicator.encode_utf8(&mut [0; 4]));
+            footnote.write_message(
                 writer,
-                "{:padding$} {} ",
-                "",
-                '>'.style(config.styles.footnote_indicator),
-                padding = left_padding
+                config,
+                self.footnote_reference(footnote, config),
+                left_padding + 2 + indicator_width,
             )?;
-            writeln!(writer, "{}", footnote)?;
         }
 
         Ok(())
@@ -299,43 +1287,367 @@ impl<'src> Diagnostic<Source<'src>> {
 
     fn write_footnotes_compact<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
-        W: Write,
+        W: StyledWrite,
     {
         for footnote in &self.footnotes {
-            write!(writer, "{} ", '>'.style(config.styles.footnote_indicator))?;
-            writeln!(writer, "{}", footnote)?;
+            let (indicator, style) = footnote.indicator(config);
+            writer.write_styled(indicator, style)?;
+            write!(writer, " ")?;
+            footnote.write_message(writer, config, self.footnote_reference(footnote, config), 2)?;
         }
 
         Ok(())
     }
 
     /// Writes this diagnostic to the given [`Write`]r using the specified [`Config`].
+    ///
+    /// If the diagnostic has no labels, only the header and the footnotes are
+    /// written. They are still aligned as if a single digit line was shown.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Source};
+    /// let diagnostic = Diagnostic::new("error: no main function")
+    ///     .with_source(Source::new("let x = 1;", Some("main.rs")));
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// assert_eq!(rendered, "error: no main function\n  @ [main.rs]\n\n");
+    ///
+    /// // footnotes are placed just like when there are labels
+    /// let diagnostic = diagnostic.with_note("add a `main` function");
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// assert_eq!(
+    ///     rendered,
+    ///     "error: no main function\n  @ [main.rs]\n  > add a `main` function\n\n",
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Besides errors from the writer, returns an error of kind
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) if the span of any
+    /// label goes past the end of the source, unless [`Config::clamp_spans`]
+    /// is enabled.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source};
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    ///
+    /// // an empty source still has a single, empty line
+    /// let source = Source::new("", Some("empty.txt"));
+    /// assert_eq!(source.line_count(), 1);
+    /// assert_eq!(source.line(0).unwrap().text(), "");
+    /// assert_eq!(source.position_at(0), Some((1, 1)));
+    ///
+    /// let mut rendered = Vec::new();
+    /// Diagnostic::new("error: no input")
+    ///     .with_source(source.clone())
+    ///     .write_to(&mut rendered, &config)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(rendered).unwrap(),
+    ///     "error: no input\n  @ [empty.txt]\n\n",
+    /// );
+    ///
+    /// let out_of_bounds = Diagnostic::new("error: expected an item")
+    ///     .with_source(source)
+    ///     .with_label(Label::new(0..1, "here"));
+    /// let error = out_of_bounds.write_to(&mut Vec::new(), &config).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
     pub fn write_to<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
         W: Write,
     {
-        self.write_header(writer, config)?;
-        self.write_body(writer, config)?;
-        self.write_footnotes(writer, config)?;
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.write_to(writer, config);
+        }
 
-        writeln!(writer)?;
-        Ok(())
+        self.prepare()?.write_to(writer, config)
+    }
+
+    /// Writes this diagnostic to the given [`std::fmt::Write`]r using the
+    /// specified [`Config`], such as a [`String`] or a
+    /// [`Formatter`](std::fmt::Formatter).
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source, SourceSpan};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let x = y;", Some("main.rs")))
+    ///     .with_label(Label::new(SourceSpan::new(8, 9), "not found"));
+    ///
+    /// let config = Config {
+    ///     color: ColorChoice::Never,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.starts_with("error: unknown variable\n"));
+    /// assert!(rendered.contains("^ not found"));
+    /// ```
+    pub fn write_to_fmt<W>(&self, writer: &mut W, config: &Config) -> std::fmt::Result
+    where
+        W: std::fmt::Write,
+    {
+        self.write_to(&mut FmtWriter(writer), config)
+            .map_err(|_| std::fmt::Error)
     }
 
     /// Writes this diagnostic to the given [`Write`]r using the specified [`Config`]
-    /// in compact mode.
+    /// in compact mode. Like [`Diagnostic::write_to`], fails if the span of any
+    /// label is out of bounds.
     pub fn write_to_compact<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
     where
         W: Write,
     {
+        self.write_compact_styled(&mut block_indented(AnsiWriter(writer), config), config)
+    }
+
+    /// Writes this diagnostic to the given [`StyledWrite`]r in compact mode.
+    fn write_compact_styled<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.write_compact_styled(writer, config);
+        }
+
+        let config = &*self.resolve_config(config, true);
         self.write_header_compact(writer, config)?;
         self.write_body_compact(writer, config)?;
         self.write_footnotes_compact(writer, config)?;
 
+        if config.trailing_newline {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes this diagnostic to the given [`Write`]r using the specified [`Config`]
+    /// in oneline mode, which writes a single `name:line:column: message` line
+    /// pointing to the primary label. This mode is meant to be consumed by
+    /// tools, so it doesn't use colors unless they're explicitly enabled.
+    ///
+    /// Columns are measured like in the body, so they match the position of
+    /// the indicators even on lines indented with tabs.
+    ///
+    /// [`Config::block_indent`] isn't applied, since tools expect the line to
+    /// start with the name of the source.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Source};
+    /// let config = Config::default().with_tab_width(8);
+    /// for (name, expected) in [(Some("src/lib.rs"), "src/lib.rs"), (None, "unknown")] {
+    ///     let source = Source::new("struct List {\n\tnext: List,\n}", name);
+    ///     let diagnostic = Diagnostic::error(source, 15..25, "recursive type has infinite size");
+    ///
+    ///     let mut rendered = Vec::new();
+    ///     diagnostic.write_to_oneline(&mut rendered, &config).unwrap();
+    ///     assert_eq!(
+    ///         String::from_utf8(rendered).unwrap(),
+    ///         format!("{expected}:2:9: error: recursive type has infinite size\n"),
+    ///     );
+    /// }
+    /// ```
+    pub fn write_to_oneline<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let writer = &mut AnsiWriter(writer);
+        let config = &*self.resolve_config(config, false);
+        writer.write_styled(
+            self.source.name().unwrap_or(&config.unknown_source_name),
+            config.styles.source_name,
+        )?;
+
+        let position = self.primary_label().and_then(|label| {
+            self.source
+                .position_at_with(label.span.start(), config.width_options())
+        });
+
+        if let Some((line, column)) = position {
+            writer.write_styled(format_args!(":{line}:{column}"), config.styles.left_column)?;
+        }
+
+        write!(writer, ": ")?;
+        self.write_message(writer, config)?;
         writeln!(writer)?;
         Ok(())
     }
 
+    /// Writes this diagnostic to the given [`Write`]r using the specified
+    /// [`Config`] in focused mode, which only shows the header and the first
+    /// line of the primary label (or the one that starts the earliest), with
+    /// its indicator. Other labels and the footnotes are not shown. Useful
+    /// for short messages such as editor tooltips.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: mismatched types")
+    ///     .with_source(Source::new("let x: u32 =\n    \"five\";", Some("main.rs")))
+    ///     .with_label(Label::new(7..10, "expected because of this"))
+    ///     .with_label(Label::primary(17..23, "found `&str`"))
+    ///     .with_note("strings are not integers");
+    ///
+    /// let mut rendered = Vec::new();
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// diagnostic.write_focused(&mut rendered, &config).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(rendered).unwrap(),
+    ///     "error: mismatched types\n  \
+    ///      @ [main.rs]\n\
+    ///      2 │  \"five\";\n  \
+    ///      :  ^^^^^^ found `&str`\n\
+    ///      \n",
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Like [`Diagnostic::write_to`], returns an error of kind
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) if the span of any
+    /// label goes past the end of the source, unless [`Config::clamp_spans`]
+    /// is enabled.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: expected `;`")
+    ///     .with_source(Source::new("let x = 1", None))
+    ///     .with_label(Label::new(8..12, "here"));
+    ///
+    /// let error = diagnostic.write_focused(&mut Vec::new(), &Config::plain()).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    ///
+    /// let config = Config::plain().with_clamp_spans(true);
+    /// assert!(diagnostic.write_focused(&mut Vec::new(), &config).is_ok());
+    /// ```
+    pub fn write_focused<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        self.write_focused_styled(&mut block_indented(AnsiWriter(writer), config), config)
+    }
+
+    /// Writes this diagnostic to the given [`StyledWrite`]r in focused mode.
+    fn write_focused_styled<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.write_focused_styled(writer, config);
+        }
+
+        let config = &*self.resolve_config(config, true);
+
+        // cut the label to its first line, so that it's shown as singleline
+        let focused: Vec<_> = self
+            .primary_label()
+            .and_then(|label| {
+                let line_index = self.source.line_index_of_byte(label.span.start())?;
+                let line_span = self.source.line(line_index)?.span();
+                let span = label.span.intersect(line_span).unwrap_or_else(|| {
+                    let start = label.span.start().min(line_span.end());
+                    SourceSpan::new(start, start)
+                });
+
+                // only keep the extra spans that are in the line shown
+                let extra_spans = label
+                    .extra_spans
+                    .iter()
+                    .copied()
+                    .filter(|extra| {
+                        line_span.start() <= extra.start() && extra.end() <= line_span.end()
+                    })
+                    .collect();
+
+                Some(Label {
+                    span,
+                    extra_spans,
+                    ..label.clone()
+                })
+            })
+            .into_iter()
+            .collect();
+
+        let left_padding = self
+            .left_padding_of(&focused)
+            .max(config.min_line_number_width.unwrap_or_default());
+        self.write_header(writer, config, left_padding)?;
+        self.write_body(
+            writer,
+            config,
+            left_padding,
+            &BodyLayout::new(&self.source, &focused),
+        )?;
+
+        if config.trailing_newline {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes this diagnostic to the given [`Write`]r using the specified
+    /// [`Config`] without its header, showing only the body and the
+    /// footnotes. Useful when the message and the name of the source were
+    /// already shown elsewhere. Like [`Diagnostic::write_to`], fails if the
+    /// span of any label is out of bounds.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let x = y;", Some("main.rs")))
+    ///     .with_label(Label::new(8..9, "not found"))
+    ///     .with_note("variables must be declared before use");
+    ///
+    /// let mut rendered = Vec::new();
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// diagnostic.write_body_only(&mut rendered, &config).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(rendered).unwrap(),
+    ///     "1 │  let x = y;\n  \
+    ///      :          ^ not found\n  \
+    ///      > variables must be declared before use\n\
+    ///      \n",
+    /// );
+    /// ```
+    pub fn write_body_only<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        self.write_body_only_styled(&mut block_indented(AnsiWriter(writer), config), config)
+    }
+
+    /// Writes this diagnostic to the given [`StyledWrite`]r in body-only
+    /// mode.
+    fn write_body_only_styled<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.write_body_only_styled(writer, config);
+        }
+
+        let config = &*self.resolve_config(config, true);
+        let left_padding = self
+            .left_padding()
+            .max(config.min_line_number_width.unwrap_or_default());
+        self.write_body(
+            writer,
+            config,
+            left_padding,
+            &BodyLayout::new(&self.source, &self.labels),
+        )?;
+        self.write_footnotes(writer, config, left_padding)?;
+
+        if config.trailing_newline {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
     /// Writes this diagnostic to `stderr` using the specified [`Config`].
     #[inline]
     pub fn eprint(&self, config: &Config) -> std::io::Result<()> {
@@ -352,4 +1664,63 @@ impl<'src> Diagnostic<Source<'src>> {
         self.write_to_compact(&mut eout, config)?;
         Ok(())
     }
+
+    /// Writes this diagnostic to `stderr` using the specified [`Config`]
+    /// in oneline mode.
+    #[inline]
+    pub fn eprint_oneline(&self, config: &Config) -> std::io::Result<()> {
+        let mut eout = BufWriter::new(std::io::stderr());
+        self.write_to_oneline(&mut eout, config)?;
+        Ok(())
+    }
+}
+
+/// Writes all of the given diagnostics to the given [`Write`]r using the
+/// specified [`Config`], separated by a blank line. When
+/// [`Config::trailing_newline`] is disabled, the separator is written
+/// between diagnostics, but not after the last one.
+///
+/// # Example
+/// ```
+/// # use yumy::{Config, Diagnostic, Source};
+/// let source = Source::new("let x = 1;", None);
+/// let diagnostics = [
+///     Diagnostic::error(source.clone(), 4..5, "first"),
+///     Diagnostic::error(source, 8..9, "second"),
+/// ];
+///
+/// let config = Config::plain().with_trailing_newline(false);
+/// let mut output = Vec::new();
+/// yumy::write_all(&diagnostics, &mut output, &config).unwrap();
+///
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.contains("\n\nerror: second\n"));
+/// assert!(!output.ends_with("\n\n"));
+/// ```
+pub fn write_all<W>(
+    diagnostics: &[Diagnostic<Source<'_>>],
+    writer: &mut W,
+    config: &Config,
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    for (index, diagnostic) in diagnostics.iter().enumerate() {
+        if index > 0 && !config.trailing_newline {
+            writeln!(writer)?;
+        }
+
+        diagnostic.write_to(writer, config)?;
+    }
+
+    Ok(())
+}
+
+/// Writes all of the given diagnostics to `stderr` using the specified
+/// [`Config`], sharing a single buffered writer between them.
+#[inline]
+pub fn eprint_all(diagnostics: &[Diagnostic<Source<'_>>], config: &Config) -> std::io::Result<()> {
+    let mut eout = BufWriter::new(std::io::stderr());
+    write_all(diagnostics, &mut eout, config)?;
+    eout.flush()
 }
@@ -1,11 +1,25 @@
-use super::{config::Config, Label};
+use super::{
+    config::{ColorChoice, Config, LineNumberAlign},
+    escape::Escaper,
+    layout::{IndicatorKind, IndicatorLayout},
+    styled::StyledWrite,
+    Label,
+};
 use crate::{
     source::{Source, SourceLine},
+    text::contains_rtl,
     SourceSpan,
 };
 use either::Either;
-use owo_colors::{OwoColorize, Style};
-use std::{io::Write, ops::Range};
+use owo_colors::Style;
+use std::{borrow::Cow, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Starts a run of text that's laid out left-to-right, isolated from the
+/// text around it.
+const LEFT_TO_RIGHT_ISOLATE: char = '\u{2066}';
+/// Ends a run started by [`LEFT_TO_RIGHT_ISOLATE`].
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
 
 #[derive(Debug, Clone, Copy)]
 struct IdentInfo {
@@ -13,18 +27,21 @@ struct IdentInfo {
     len: usize,
 }
 
-fn ident_info(text: &str) -> IdentInfo {
+/// Returns where the indentation of the given text ends and its width. Only
+/// graphemes that are exactly a space or a tab count as indentation, so a
+/// space carrying a combining mark is treated as part of the content.
+fn ident_info(text: &str, tab_width: usize) -> IdentInfo {
     let mut len = 0;
-    let mut c_indices = text.char_indices();
+    let mut graphemes = text.grapheme_indices(true);
 
     let end = loop {
-        let Some((start, c)) = c_indices.next() else {
+        let Some((start, g)) = graphemes.next() else {
             break text.len();
         };
 
-        len += match c {
-            ' ' => 1,
-            '\t' => 4,
+        len += match g {
+            " " => 1,
+            "\t" => tab_width,
             _ => break start,
         }
     };
@@ -33,29 +50,46 @@ fn ident_info(text: &str) -> IdentInfo {
 }
 
 #[derive(Debug, Clone)]
-struct SinglelineLabel {
-    message: String,
+struct SinglelineLabel<'a> {
+    ordinal: usize,
+    message: &'a str,
+    note: Option<&'a str>,
     line: u32,
     line_span: SourceSpan,
+    /// Spans of the same label in the same line, shown in the same row.
+    extra_line_spans: Vec<SourceSpan>,
     indicator_style: Option<Style>,
+    order: i32,
+}
+
+impl SinglelineLabel<'_> {
+    /// Returns all of the spans of this label in its line.
+    fn line_spans(&self) -> impl Iterator<Item = SourceSpan> + '_ {
+        std::iter::once(self.line_span).chain(self.extra_line_spans.iter().copied())
+    }
 }
 
 #[derive(Debug, Clone)]
-struct MultilineLabel {
-    message: String,
+struct MultilineLabel<'a> {
+    ordinal: usize,
+    message: &'a str,
+    note: Option<&'a str>,
     line_range: Range<u32>,
+    /// The span of this label in the source, used to order labels that
+    /// start and end in the same lines.
+    span: SourceSpan,
     indicator_style: Option<Style>,
 }
 
 #[derive(Debug, Clone, Default)]
-enum Slot {
-    RecentlyAdded(MultilineLabel),
-    Active(MultilineLabel),
+enum Slot<'a> {
+    RecentlyAdded(&'a MultilineLabel<'a>),
+    Active(&'a MultilineLabel<'a>),
     #[default]
     Inactive,
 }
 
-impl Slot {
+impl<'a> Slot<'a> {
     pub fn is_active(&self) -> bool {
         match self {
             Slot::RecentlyAdded(_) => true,
@@ -64,7 +98,7 @@ impl Slot {
         }
     }
 
-    pub fn unwrap_label(self) -> MultilineLabel {
+    pub fn unwrap_label(self) -> &'a MultilineLabel<'a> {
         match self {
             Slot::RecentlyAdded(label) => label,
             Slot::Active(label) => label,
@@ -73,33 +107,32 @@ impl Slot {
     }
 }
 
-/// Struct that takes care of emitting the body of a diagnostic.
-/// Keeping the state for this in it's own struct is easier.
-pub(crate) struct BodyWriter<'src, W> {
-    writer: W,
-    source: Source<'src>,
-    config: Config,
-    left_padding: usize,
-    ident_len: usize,
-    singleline_labels: Vec<SinglelineLabel>,
-    multiline_labels: Vec<MultilineLabel>,
-    multiline_slots: Vec<Slot>,
-    current_line: u32,
+/// The layout of the labels in the body of a diagnostic. This doesn't depend
+/// on the config used to render it, so it can be computed once and reused.
+#[derive(Debug, Clone)]
+pub(crate) struct BodyLayout<'a> {
+    singleline_labels: Vec<SinglelineLabel<'a>>,
+    multiline_labels: Vec<MultilineLabel<'a>>,
+    /// Whether each label is primary, by ordinal.
+    primary: Vec<bool>,
+    slots_needed: usize,
 }
 
-impl<'src, W> BodyWriter<'src, W>
-where
-    W: Write,
-{
+impl<'a> BodyLayout<'a> {
     /// Calculates the number of slots needed for a set
     /// of multiline labels.
-    fn slots_needed(labels: &[MultilineLabel]) -> usize {
+    fn slots_needed<'l, 'm: 'l, I>(labels: I) -> usize
+    where
+        I: IntoIterator<Item = &'l MultilineLabel<'m>>,
+    {
         let mut events: Vec<_> = labels
-            .iter()
+            .into_iter()
             .flat_map(|x| [(true, x.line_range.start), (false, x.line_range.end)])
             .collect();
 
-        events.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+        // labels that end in a line free their slot before the ones that
+        // start in it need one, so ends must come first
+        events.sort_unstable_by_key(|&(start, line)| (line, start));
 
         let mut current = 0;
         let mut max = 0;
@@ -119,72 +152,343 @@ where
         max
     }
 
+    pub(crate) fn new(source: &Source, labels: &'a [Label]) -> Self {
+        Self::filtered(source, labels, |_| true)
+    }
+
+    /// Computes the layout of the labels that match the given filter. The
+    /// labels keep their ordinals, so their numbers are the same as if all
+    /// of them were shown.
+    pub(crate) fn filtered<F>(source: &Source, labels: &'a [Label], filter: F) -> Self
+    where
+        F: Fn(&Label) -> bool,
+    {
+        let mut singleline_labels = Vec::new();
+        let mut multiline_labels = Vec::new();
+        for (ordinal, label) in labels.iter().enumerate() {
+            if !filter(label) {
+                continue;
+            }
+
+            // the message goes with the main span, the extra ones only get
+            // their indicators
+            for (part, span) in label.spans().enumerate() {
+                let (message, note) = if part == 0 {
+                    (label.message.as_str(), label.note.as_deref())
+                } else {
+                    ("", None)
+                };
+
+                let line_range = source
+                    .line_range_of_span(span)
+                    .expect("span should be in bounds");
+
+                if line_range.start + 1 == line_range.end {
+                    let line_index = line_range.start;
+                    let line = source.line(line_index).unwrap();
+
+                    let label_line_start = span.start() - line.span.start();
+                    let label_line_end = label_line_start + span.len();
+                    let line_span = SourceSpan::new(label_line_start, label_line_end);
+
+                    // spans of the same label in the same line share a row
+                    let same_row =
+                        singleline_labels
+                            .iter_mut()
+                            .find(|other: &&mut SinglelineLabel| {
+                                other.ordinal == ordinal && other.line == line_index
+                            });
+
+                    if let Some(other) = same_row {
+                        other.extra_line_spans.push(line_span);
+                        continue;
+                    }
+
+                    singleline_labels.push(SinglelineLabel {
+                        ordinal,
+                        message,
+                        note,
+                        line: line_index,
+                        line_span,
+                        extra_line_spans: Vec::new(),
+                        indicator_style: label.indicator_style,
+                        order: label.order.unwrap_or(0),
+                    });
+                } else {
+                    multiline_labels.push(MultilineLabel {
+                        ordinal,
+                        message,
+                        note,
+                        line_range,
+                        span,
+                        indicator_style: label.indicator_style,
+                    });
+                }
+            }
+        }
+
+        Self {
+            slots_needed: Self::slots_needed(&multiline_labels),
+            singleline_labels,
+            multiline_labels,
+            primary: labels.iter().map(|label| label.primary).collect(),
+        }
+    }
+}
+
+impl BodyLayout<'_> {
+    /// Returns whether there are no labels in this layout.
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.singleline_labels.is_empty() && self.multiline_labels.is_empty()
+    }
+}
+
+/// Writer that holds back the last row written to it, so that its gutter
+/// can be replaced once it's known to be the last one. Rows are only held
+/// back if `hold_last_row` is set.
+struct RowWriter<W> {
+    inner: W,
+    hold_last_row: bool,
+    current: Vec<(String, Style)>,
+    current_gutter: Range<usize>,
+    last: Option<Vec<(String, Style)>>,
+    last_gutter: Range<usize>,
+}
+
+impl<W> RowWriter<W>
+where
+    W: StyledWrite,
+{
+    fn new(inner: W, hold_last_row: bool) -> Self {
+        Self {
+            inner,
+            hold_last_row,
+            current: Vec::new(),
+            current_gutter: 0..0,
+            last: None,
+            last_gutter: 0..0,
+        }
+    }
+
+    /// Marks the start of the gutter in the current row.
+    #[inline]
+    fn mark_gutter_start(&mut self) {
+        self.current_gutter.start = self.current.len();
+    }
+
+    /// Marks the end of the gutter in the current row.
+    #[inline]
+    fn mark_gutter_end(&mut self) {
+        self.current_gutter.end = self.current.len();
+    }
+
+    /// Writes the given segments to the inner writer, followed by a line
+    /// break if they're a whole row.
+    fn write_segments(&mut self, segments: &[(String, Style)], row: bool) -> std::io::Result<()> {
+        for (text, style) in segments {
+            self.inner.write_styled(text, *style)?;
+        }
+
+        if row {
+            writeln!(self.inner)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the rows that are being held back, replacing the gutter of the
+    /// last one with `bottom` if given.
+    fn finish(mut self, bottom: Option<(char, Style)>) -> std::io::Result<()> {
+        if let Some(mut last) = self.last.take() {
+            if let Some((bottom, style)) = bottom {
+                last.splice(self.last_gutter.clone(), [(bottom.to_string(), style)]);
+            }
+
+            self.write_segments(&last, true)?;
+        }
+
+        let current = std::mem::take(&mut self.current);
+        self.write_segments(&current, false)
+    }
+}
+
+impl<W> StyledWrite for RowWriter<W>
+where
+    W: StyledWrite,
+{
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: std::fmt::Display,
+    {
+        if !self.hold_last_row {
+            return self.inner.write_styled(text, style);
+        }
+
+        let text = text.to_string();
+        for row in text.split_inclusive('\n') {
+            let (row, ends_row) = match row.strip_suffix('\n') {
+                Some(row) => (row, true),
+                None => (row, false),
+            };
+
+            if !row.is_empty() {
+                self.current.push((row.to_owned(), style));
+            }
+
+            if ends_row {
+                if let Some(last) = self.last.take() {
+                    self.write_segments(&last, true)?;
+                }
+
+                self.last = Some(std::mem::take(&mut self.current));
+                self.last_gutter = std::mem::replace(&mut self.current_gutter, 0..0);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Struct that takes care of emitting the body of a diagnostic.
+/// Keeping the state for this in its own struct is easier.
+pub(crate) struct BodyWriter<'a, 'src, W> {
+    writer: RowWriter<W>,
+    source: &'a Source<'src>,
+    config: &'a Config,
+    left_padding: usize,
+    ident_len: usize,
+    singleline_labels: Vec<&'a SinglelineLabel<'a>>,
+    /// Every singleline label shown, which unlike `singleline_labels` are
+    /// kept as they're emitted.
+    shown_singleline_labels: Vec<&'a SinglelineLabel<'a>>,
+    multiline_labels: Vec<&'a MultilineLabel<'a>>,
+    multiline_slots: Vec<Slot<'a>>,
+    /// The style of the indicators of each label, by ordinal.
+    indicator_styles: Vec<Option<Style>>,
+    current_line: u32,
+    /// The byte range of the current line that is shown, if the line is too
+    /// wide to be shown whole.
+    line_window: Option<Range<usize>>,
+    /// The number of labels that aren't shown because of
+    /// [`Config::max_labels`].
+    hidden_labels: usize,
+    /// Whether the gutter of a row has been emitted already.
+    emitted_gutter: bool,
+}
+
+impl<'a, 'src, W> BodyWriter<'a, 'src, W>
+where
+    W: StyledWrite,
+{
     pub(crate) fn new(
         writer: W,
-        source: Source<'src>,
-        config: &Config,
+        source: &'a Source<'src>,
+        config: &'a Config,
         left_padding: usize,
-        labels: &[Label],
+        layout: &'a BodyLayout<'a>,
     ) -> Self {
-        let mut singleline_labels = Vec::new();
-        let mut multiline_labels = Vec::new();
-        for label in labels {
-            if label.is_singleline(&source) {
-                let line_index = label.line_range(&source).start;
-                let line = source.line(line_index).unwrap();
-
-                let label_line_start = label.span.start() - line.span.start();
-                let label_line_end = label_line_start + label.span.len();
-
-                let label = SinglelineLabel {
-                    message: label.message.clone(),
-                    line: label.line_range(&source).start,
-                    line_span: SourceSpan::new(label_line_start, label_line_end),
-                    indicator_style: label.indicator_style,
-                };
-                singleline_labels.push(label);
-            } else {
-                let label = MultilineLabel {
-                    message: label.message.clone(),
-                    line_range: label.line_range(&source),
-                    indicator_style: label.indicator_style,
-                };
+        let primary = &layout.primary;
+        let mut singleline_labels: Vec<_> = layout.singleline_labels.iter().collect();
+        let mut multiline_labels: Vec<_> = layout.multiline_labels.iter().collect();
+        let mut slots_needed = layout.slots_needed;
+
+        // only keep the labels that come first in the source
+        let mut hidden_labels = 0;
+        if let Some(max_labels) = config.max_labels {
+            let mut starts: Vec<_> = singleline_labels
+                .iter()
+                .map(|label| (label.line, label.ordinal))
+                .chain(
+                    multiline_labels
+                        .iter()
+                        .map(|label| (label.line_range.start, label.ordinal)),
+                )
+                .collect();
+
+            // labels with extra spans have an entry for each of them, but
+            // only count once, where they first appear
+            starts.sort_unstable();
+            let mut seen = vec![false; primary.len()];
+            starts.retain(|&(_, ordinal)| !std::mem::replace(&mut seen[ordinal], true));
+
+            if starts.len() > max_labels {
+                hidden_labels = starts.len() - max_labels;
+
+                let mut shown = vec![false; primary.len()];
+                for &(_, ordinal) in &starts[..max_labels] {
+                    shown[ordinal] = true;
+                }
 
-                multiline_labels.push(label);
+                singleline_labels.retain(|label| shown[label.ordinal]);
+                multiline_labels.retain(|label| shown[label.ordinal]);
+                slots_needed = BodyLayout::slots_needed(multiline_labels.iter().copied());
             }
         }
 
+        let mut indicator_styles = vec![None; primary.len()];
+        let label_styles = singleline_labels
+            .iter()
+            .map(|label| (label.ordinal, label.indicator_style))
+            .chain(
+                multiline_labels
+                    .iter()
+                    .map(|label| (label.ordinal, label.indicator_style)),
+            );
+
+        for (ordinal, style) in label_styles {
+            indicator_styles[ordinal] = style
+                .or(primary[ordinal].then_some(config.styles.primary_indicator))
+                .filter(|_| config.color != ColorChoice::Never);
+        }
+
         let singleline_lines = singleline_labels
             .iter()
             .map(|label| source.line(label.line).unwrap());
-        let multiline_lines = multiline_labels.iter().flat_map(|label| {
-            [
-                source.line(label.line_range.start).unwrap(),
-                source.line(label.line_range.end - 1).unwrap(),
-            ]
-        });
-
-        let ident_width = singleline_lines
-            .chain(multiline_lines)
-            .map(|line| ident_info(line.line).len)
-            .min()
-            .unwrap_or(0);
+        let multiline_lines = multiline_labels
+            .iter()
+            .flat_map(|label| label.line_range.clone())
+            .map(|index| source.line(index).unwrap());
+
+        // every line inside of a multiline label is shown, so all of them
+        // count, except for blank ones which have no text to align
+        let ident_width = if config.dedent {
+            singleline_lines
+                .chain(multiline_lines)
+                .map(|line| {
+                    (
+                        line.line.len(),
+                        ident_info(line.line, config.indent_tab_width()),
+                    )
+                })
+                .filter(|(len, info)| info.end < *len)
+                .map(|(_, info)| info.len)
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
 
         Self {
-            writer,
+            writer: RowWriter::new(writer, config.charset.gutter_bottom.is_some()),
             source,
-            config: config.clone(),
+            config,
             left_padding,
             ident_len: ident_width,
+            shown_singleline_labels: singleline_labels.clone(),
             singleline_labels,
-            multiline_slots: vec![Slot::Inactive; Self::slots_needed(&multiline_labels)],
+            multiline_slots: vec![Slot::Inactive; slots_needed],
             multiline_labels,
+            indicator_styles,
             current_line: 0,
+            line_window: None,
+            hidden_labels,
+            emitted_gutter: false,
         }
     }
 
     /// Returns the next label, be it single or multi line.
-    fn next_label(&mut self) -> Option<Either<SinglelineLabel, MultilineLabel>> {
+    fn next_label(&mut self) -> Option<Either<&'a SinglelineLabel<'a>, &'a MultilineLabel<'a>>> {
         let next_singleline_label = self
             .singleline_labels
             .iter()
@@ -218,7 +522,7 @@ where
     }
 
     /// Returns the next singleline label in the current line.
-    fn next_singleline_label_in_current(&mut self) -> Option<SinglelineLabel> {
+    fn next_singleline_label_in_current(&mut self) -> Option<&'a SinglelineLabel<'a>> {
         self.singleline_labels
             .iter()
             .enumerate()
@@ -228,7 +532,7 @@ where
     }
 
     /// Returns the next multiline label starting in the current line.
-    fn next_multiline_label_in_current(&mut self) -> Option<MultilineLabel> {
+    fn next_multiline_label_in_current(&mut self) -> Option<&'a MultilineLabel<'a>> {
         self.multiline_labels
             .iter()
             .enumerate()
@@ -243,9 +547,34 @@ where
         self.multiline_slots.iter().any(|x| x.is_active())
     }
 
-    /// Allocate the given multiline label into an available slot.
+    /// Returns the byte index of each grapheme of the given line after its
+    /// indentation, together with what should be displayed for it. The text
+    /// is escaped as a whole, so escape sequences are recognized even if they
+    /// cross the bounds of the ranges of the line that are shown or measured.
+    fn shown_graphemes<'l>(
+        &self,
+        line: SourceLine<'l>,
+    ) -> impl Iterator<Item = (usize, Cow<'l, str>)> {
+        let text_start = ident_info(line.line, self.config.indent_tab_width()).end;
+        Escaper::new(self.config)
+            .graphemes(&line.line[text_start..])
+            .map(move |(index, shown)| (text_start + index, shown))
+    }
+
+    /// Returns the display width of the given byte range of a line, as shown.
+    fn display_width(&self, line: SourceLine, range: Range<usize>) -> usize {
+        let options = self.config.width_options();
+        self.shown_graphemes(line)
+            .skip_while(|(index, _)| *index < range.start)
+            .take_while(|(index, _)| *index < range.end)
+            .map(|(_, shown)| options.display_width(&shown))
+            .sum()
+    }
+
+    /// Allocate the given multiline label into an available slot. If there
+    /// are no available slots (which shouldn't happen), a new one is added.
     #[inline]
-    fn allocate_multiline_label(&mut self, label: MultilineLabel) {
+    fn allocate_multiline_label(&mut self, label: &'a MultilineLabel<'a>) {
         let slot_index = self
             .multiline_slots
             .iter()
@@ -256,36 +585,52 @@ where
         if let Some(slot_index) = slot_index {
             self.multiline_slots[slot_index] = Slot::RecentlyAdded(label);
         } else {
-            unreachable!("should have enough slots")
+            self.multiline_slots.push(Slot::RecentlyAdded(label));
         }
     }
 
     /// Emit the left column of the body.
     #[inline]
-    fn emit_left_column(&mut self, line_index: impl Into<Option<usize>>) -> std::io::Result<()> {
-        if let Some(index) = line_index.into() {
-            write!(
-                self.writer,
-                "{:padding$} {} ",
-                (index + 1).style(self.config.styles.left_column),
-                self.config
-                    .charset
-                    .vertical_bar
-                    .style(self.config.styles.left_column),
-                padding = self.left_padding
-            )?;
+    fn emit_left_column(&mut self, line_index: impl Into<Option<u32>>) -> std::io::Result<()> {
+        let gutter = if let Some(index) = line_index.into() {
+            let line_number = self.source.line_number(index);
+            let padding = self.left_padding;
+            let style = self.config.styles.left_column;
+
+            match self.config.line_number_align {
+                LineNumberAlign::Left => self
+                    .writer
+                    .write_styled(format_args!("{line_number:<padding$}"), style)?,
+                LineNumberAlign::Right => self
+                    .writer
+                    .write_styled(format_args!("{line_number:>padding$}"), style)?,
+            }
+
+            write!(self.writer, " ")?;
+
+            self.config.charset.vertical_bar
         } else {
-            write!(
-                self.writer,
-                "{:padding$} {} ",
-                "",
-                self.config
-                    .charset
-                    .separator
-                    .style(self.config.styles.left_column),
-                padding = self.left_padding
-            )?;
-        }
+            write!(self.writer, "{:padding$} ", "", padding = self.left_padding)?;
+            self.config.charset.separator
+        };
+
+        self.emit_gutter(gutter)?;
+        write!(self.writer, " ")
+    }
+
+    /// Emit the given gutter character, or the top corner of the gutter if
+    /// this is the first row of the body.
+    fn emit_gutter(&mut self, gutter: char) -> std::io::Result<()> {
+        let gutter = match self.config.charset.gutter_top {
+            Some(top) if !self.emitted_gutter => top,
+            _ => gutter,
+        };
+
+        self.emitted_gutter = true;
+        self.writer.mark_gutter_start();
+        self.writer
+            .write_styled(gutter, self.config.styles.left_column)?;
+        self.writer.mark_gutter_end();
 
         Ok(())
     }
@@ -312,8 +657,7 @@ where
                 }
             };
 
-            let style = label
-                .indicator_style
+            let style = self.indicator_styles[label.ordinal]
                 .unwrap_or(self.config.styles.multiline_indicator);
 
             let indicator_char = if is_new {
@@ -324,69 +668,544 @@ where
                 self.config.charset.vertical_bar
             };
 
-            write!(self.writer, "{}", indicator_char.style(style))?;
+            self.writer.write_styled(indicator_char, style)?;
         }
 
         Ok(())
     }
 
+    /// Calculates the byte range of the given text (which starts at `base` in
+    /// its line) that fits in `max_width` columns, centered on `focus`.
+    fn focus_window(
+        &self,
+        line: SourceLine,
+        focus: Range<usize>,
+        max_width: usize,
+    ) -> Range<usize> {
+        let options = self.config.width_options();
+        let graphemes: Vec<_> = self
+            .shown_graphemes(line)
+            .map(|(index, shown)| (index, options.display_width(&shown)))
+            .collect();
+
+        let grapheme_at = |byte: usize| {
+            graphemes
+                .iter()
+                .position(|(index, _)| *index >= byte)
+                .unwrap_or(graphemes.len())
+        };
+
+        let mut start = grapheme_at(focus.start);
+        let mut end = grapheme_at(focus.end).max(start + 1).min(graphemes.len());
+        let mut width: usize = graphemes[start..end].iter().map(|(_, w)| w).sum();
+
+        // if the focus itself doesn't fit, keep its start
+        while width > max_width && end > start + 1 {
+            end -= 1;
+            width -= graphemes[end].1;
+        }
+
+        // expand to the left up to half of the remaining width, then to the
+        // right, and then to the left again with whatever is left
+        let left_budget = max_width.saturating_sub(width) / 2;
+        let mut left_width = 0;
+        while start > 0 && left_width + graphemes[start - 1].1 <= left_budget {
+            start -= 1;
+            left_width += graphemes[start].1;
+        }
+        width += left_width;
+
+        while end < graphemes.len() && width + graphemes[end].1 <= max_width {
+            width += graphemes[end].1;
+            end += 1;
+        }
+
+        while start > 0 && width + graphemes[start - 1].1 <= max_width {
+            start -= 1;
+            width += graphemes[start].1;
+        }
+
+        let byte_at = |grapheme: usize| {
+            graphemes
+                .get(grapheme)
+                .map(|(index, _)| *index)
+                .unwrap_or(line.line.len())
+        };
+
+        byte_at(start)..byte_at(end)
+    }
+
+    /// Calculates the window of the given line that should be shown, if it
+    /// is wider than the maximum line width.
+    fn line_window(&self, line: SourceLine, line_index: u32) -> Option<Range<usize>> {
+        let max_width = self.config.max_line_width?;
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        if self.display_width(line, line_ident_info.end..line.line.len()) <= max_width {
+            return None;
+        }
+
+        let mut spans: Vec<_> = self
+            .singleline_labels
+            .iter()
+            .filter(|label| label.line == line_index)
+            .flat_map(|label| label.line_spans())
+            .map(|span| {
+                let start = (span.start() as usize).max(line_ident_info.end);
+                let end = (span.end() as usize).max(start);
+                start..end
+            })
+            .collect();
+
+        // multiline labels are pointed at where they start and end, be they
+        // allocated already or not
+        let line_start = line.span.start();
+        let allocated = self.multiline_slots.iter().filter_map(|slot| match slot {
+            Slot::RecentlyAdded(label) | Slot::Active(label) => Some(*label),
+            Slot::Inactive => None,
+        });
+
+        for label in self.multiline_labels.iter().copied().chain(allocated) {
+            if label.line_range.start == line_index {
+                let start = (label.span.start() - line_start) as usize;
+                spans.push(start.max(line_ident_info.end)..(start + 1).min(line.line.len()));
+            }
+
+            if label.line_range.end - 1 == line_index {
+                let end = ((label.span.end() - line_start) as usize).min(line.line.len());
+                spans.push(end.saturating_sub(1).max(line_ident_info.end)..end);
+            }
+        }
+        spans.sort_unstable_by_key(|span| span.start);
+
+        let mut spans = spans.into_iter();
+        let focus = spans.next().map(|first| {
+            // try to cover all labels, falling back to the first one
+            let covering = spans.fold(first.clone(), |acc, span| {
+                acc.start.min(span.start)..acc.end.max(span.end)
+            });
+
+            if self.display_width(line, covering.clone()) <= max_width {
+                covering
+            } else {
+                first
+            }
+        });
+
+        let focus = focus.unwrap_or(line_ident_info.end..line_ident_info.end);
+        Some(self.focus_window(line, focus, max_width))
+    }
+
+    /// Returns the width of the given line as shown, excluding the
+    /// indentation.
+    fn shown_width(&self, line: SourceLine) -> usize {
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        match &self.line_window {
+            Some(window) => {
+                let ellipses = usize::from(window.start > line_ident_info.end)
+                    + usize::from(window.end < line.line.len());
+                self.display_width(line, window.clone()) + ellipses
+            }
+            None => self.display_width(line, line_ident_info.end..line.line.len()),
+        }
+    }
+
+    /// Splits the given window of a line into segments, each with the style
+    /// it should be rendered with. If source highlighting is enabled, parts
+    /// covered by singleline labels use the style of the innermost label.
+    fn source_segments(
+        &self,
+        line_index: u32,
+        window: Range<usize>,
+        style: Style,
+    ) -> Vec<(Range<usize>, Style)> {
+        if !self.config.highlight_source {
+            return vec![(window, style)];
+        }
+
+        let labels: Vec<_> = self
+            .singleline_labels
+            .iter()
+            .filter(|label| label.line == line_index)
+            .flat_map(|label| {
+                let style = self.indicator_styles[label.ordinal]
+                    .unwrap_or(self.config.styles.highlighted_source);
+
+                label.line_spans().map(move |span| {
+                    let start = (span.start() as usize).clamp(window.start, window.end);
+                    let end = (span.end() as usize).clamp(start, window.end);
+                    (start..end, style)
+                })
+            })
+            .collect();
+
+        let mut boundaries: Vec<_> = labels
+            .iter()
+            .flat_map(|(range, _)| [range.start, range.end])
+            .chain([window.start, window.end])
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        boundaries
+            .windows(2)
+            .map(|bounds| {
+                let segment = bounds[0]..bounds[1];
+                let style = labels
+                    .iter()
+                    .filter(|(range, _)| range.start <= segment.start && segment.end <= range.end)
+                    .min_by_key(|(range, _)| range.len())
+                    .map(|(_, style)| *style)
+                    .unwrap_or(style);
+
+                (segment, style)
+            })
+            .collect()
+    }
+
     /// Emit the given source line.
     fn emit_source_line(&mut self, line: SourceLine, line_index: u32) -> std::io::Result<()> {
-        self.emit_left_column(line_index as usize)?;
+        self.line_window = self.line_window(line, line_index);
+        if let Some(window) = self.line_window.clone().filter(|_| self.config.show_ruler) {
+            self.emit_window_ruler(line, window)?;
+        }
+
+        self.emit_left_column(line_index)?;
         self.emit_multiline_indicators()?;
 
-        let line_ident_info = ident_info(line.line);
-        let spaces = line_ident_info.len - self.ident_len;
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        let spaces = line_ident_info.len.saturating_sub(self.ident_len);
 
-        let style = self.source.style().unwrap_or(self.config.styles.source);
+        let style = self
+            .source
+            .style()
+            .filter(|_| self.config.color != ColorChoice::Never)
+            .unwrap_or(self.config.styles.source);
+
+        let window = self
+            .line_window
+            .clone()
+            .unwrap_or(line_ident_info.end..line.line.len());
+
+        let segments = self.source_segments(line_index, window.clone(), style);
 
         write!(self.writer, "{:x$} ", "", x = spaces)?;
-        writeln!(
-            self.writer,
-            "{}",
-            (&line.line[line_ident_info.end..]).style(style),
-        )?;
+        if window.start > line_ident_info.end {
+            self.writer
+                .write_styled(self.config.charset.ellipsis, self.config.styles.left_column)?;
+        }
+
+        // keep terminals from reordering right-to-left text relative to the
+        // indicators below it
+        let isolate = self.config.bidi_isolate && contains_rtl(&line.line[window.clone()]);
+        if isolate {
+            write!(self.writer, "{LEFT_TO_RIGHT_ISOLATE}")?;
+        }
+
+        let mut graphemes = self
+            .shown_graphemes(line)
+            .skip_while(|(index, _)| *index < window.start)
+            .peekable();
+
+        for (range, style) in segments {
+            let mut text = String::new();
+            while let Some((_, shown)) = graphemes.next_if(|(index, _)| *index < range.end) {
+                match &*shown {
+                    "\t" if self.config.expand_tabs => {
+                        text.extend(std::iter::repeat_n(' ', self.config.tab_width))
+                    }
+                    shown => text.push_str(shown),
+                }
+            }
+
+            self.writer.write_styled(text, style)?;
+        }
+
+        if isolate {
+            write!(self.writer, "{POP_DIRECTIONAL_ISOLATE}")?;
+        }
+
+        if window.end < line.line.len() {
+            self.writer
+                .write_styled(self.config.charset.ellipsis, self.config.styles.left_column)?;
+        }
+
+        writeln!(self.writer)?;
         Ok(())
     }
 
+    /// Returns where the underliner of the given span of a line starts,
+    /// relative to the end of the multiline indicators, and its width.
+    fn underliner_columns(&self, line: SourceLine, line_span: SourceSpan) -> (usize, usize) {
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        let spaces = line_ident_info.len.saturating_sub(self.ident_len);
+
+        let window = self
+            .line_window
+            .clone()
+            .unwrap_or(line_ident_info.end..line.line.len());
+
+        // the span might start inside the indentation, which isn't rendered,
+        // or outside of the window
+        let start = (line_span.start() as usize).clamp(window.start, window.end);
+        let end = (line_span.end() as usize).clamp(start, window.end);
+
+        let leading_ellipsis = window.start > line_ident_info.end;
+        let trailing_ellipsis = window.end < line.line.len();
+
+        // the columns of the rendered indentation the span covers, if it
+        // starts inside of it. indentation that was dedented away isn't shown.
+        let span_start = line_span.start() as usize;
+        let span_end = line_span.end() as usize;
+        let indent_columns = if !leading_ellipsis && span_start < line_ident_info.end {
+            let column_of = |byte: usize| {
+                let indent = &line.line[..byte.min(line_ident_info.end)];
+                ident_info(indent, self.config.indent_tab_width())
+                    .len
+                    .max(self.ident_len)
+                    - self.ident_len
+            };
+
+            Some(column_of(span_start)..column_of(span_end))
+                .filter(|columns| columns.start < spaces)
+        } else {
+            None
+        };
+
+        if leading_ellipsis && span_end <= window.start {
+            // point at the leading ellipsis
+            (spaces + 1, 1)
+        } else if trailing_ellipsis && span_start >= window.end {
+            // point at the trailing ellipsis
+            (spaces + self.shown_width(line), 1)
+        } else if let Some(columns) = indent_columns {
+            if span_end > line_ident_info.end {
+                // underline the rest of the indentation, the space between
+                // it and the text and then the text itself
+                let text_width = self.display_width(line, start..end);
+                (columns.start, spaces - columns.start + 1 + text_width)
+            } else {
+                (columns.start, columns.len().max(1))
+            }
+        } else {
+            // empty spans (such as a label on an empty line) still get a
+            // single caret, pointing right where they are
+            (
+                spaces
+                    + 1
+                    + usize::from(leading_ellipsis)
+                    + self.display_width(line, window.start..start),
+                self.display_width(line, start..end).max(1),
+            )
+        }
+    }
+
+    /// Widens the given underliner of a span of a line to
+    /// [`Config::min_underline_width`], growing it on both sides as evenly as
+    /// the bounds of the line and the given columns allow.
+    fn widen_underliner(
+        &self,
+        line: SourceLine,
+        (start, width): (usize, usize),
+        columns: Range<usize>,
+    ) -> (usize, usize) {
+        let min_width = self.config.min_underline_width;
+        if width >= min_width {
+            return (start, width);
+        }
+
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        let text_start = line_ident_info.len.saturating_sub(self.ident_len) + 1;
+        let lower = start.min(text_start).max(columns.start.min(start));
+        let upper = (start + width)
+            .max(text_start + self.shown_width(line))
+            .min(columns.end.max(start + width));
+
+        let widened = min_width.min(upper - lower).max(width);
+        let start = start
+            .saturating_sub((widened - width) / 2)
+            .clamp(lower, upper - widened);
+
+        (start, widened)
+    }
+
+    /// Returns the underliners of the spans of the given singleline label,
+    /// ordered by their columns.
+    fn underliners(&self, line: SourceLine, label: &SinglelineLabel) -> Vec<(usize, usize)> {
+        let mut spans: Vec<_> = label
+            .line_spans()
+            .map(|span| self.underliner_columns(line, span))
+            .collect();
+        spans.sort_unstable();
+
+        let others: Vec<_> = self
+            .shown_singleline_labels
+            .iter()
+            .filter(|other| other.line == label.line && other.ordinal != label.ordinal)
+            .flat_map(|other| other.line_spans())
+            .map(|span| self.underliner_columns(line, span))
+            .collect();
+
+        // the gap between the underliners of two labels is split between
+        // them, keeping at least a column in the middle free
+        let share = |gap: usize| gap.saturating_sub(1) / 2;
+
+        // widened underliners keep a column between them and their
+        // neighbours, so that they don't run into each other
+        let mut underliners: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+        for (index, &(start, width)) in spans.iter().enumerate() {
+            let end = start + width;
+            let lower = underliners
+                .last()
+                .map(|(start, width)| start + width + 1)
+                .into_iter()
+                .chain(
+                    others
+                        .iter()
+                        .map(|&(other_start, other_width)| other_start + other_width)
+                        .filter(|&other_end| other_end <= start)
+                        .map(|other_end| start - share(start - other_end)),
+                )
+                .max()
+                .unwrap_or(0);
+            let upper = spans
+                .get(index + 1)
+                .map(|(start, _)| start.saturating_sub(1))
+                .into_iter()
+                .chain(
+                    others
+                        .iter()
+                        .map(|&(other_start, _)| other_start)
+                        .filter(|&other_start| other_start >= end)
+                        .map(|other_start| end + share(other_start - end)),
+                )
+                .min()
+                .unwrap_or(usize::MAX);
+
+            underliners.push(self.widen_underliner(line, (start, width), lower..upper));
+        }
+
+        underliners
+    }
+
     /// Emit the given singleline label.
     fn emit_singleline_label(
         &mut self,
         line: SourceLine,
-        label: SinglelineLabel,
+        label: &'a SinglelineLabel<'a>,
     ) -> std::io::Result<()> {
         self.emit_left_column(None)?;
         self.emit_multiline_indicators()?;
 
-        let line_ident_info = ident_info(line.line);
-        let spaces = line_ident_info.len - self.ident_len;
+        let underliners = self.underliners(line, label);
+        let style =
+            self.indicator_styles[label.ordinal].unwrap_or(self.config.styles.singleline_indicator);
+
+        // the underliners of extra spans might overlap, in which case only
+        // the part that wasn't drawn yet is
+        let mut column = 0;
+        for (start, width) in underliners {
+            let end = start + width;
+            if end <= column {
+                continue;
+            }
 
-        let line_width = unicode_width::UnicodeWidthStr::width(line.line);
+            let first = if start >= column {
+                write!(self.writer, "{:x$}", "", x = start - column)?;
+                self.config.charset.underline_start
+            } else {
+                self.config.charset.underliner
+            };
 
-        let before_underliner_width = spaces
-            + unicode_width::UnicodeWidthStr::width(&line.line[..label.line_span.start() as usize]);
-        let after_underliner_width =
-            unicode_width::UnicodeWidthStr::width(&line.line[label.line_span.end() as usize..]);
-        let underliner_width = line_width - (before_underliner_width + after_underliner_width);
+            let underliner = std::iter::once(first)
+                .chain(std::iter::repeat(self.config.charset.underliner))
+                .take(end - start.max(column));
 
-        let before = std::iter::repeat(' ').take(before_underliner_width);
-        let underliner = std::iter::repeat(self.config.charset.underliner).take(underliner_width);
+            for c in underliner {
+                self.writer.write_styled(c, style)?;
+            }
 
-        let underliner = before.chain(underliner);
+            column = end;
+        }
 
-        let style = label
-            .indicator_style
-            .unwrap_or(self.config.styles.singleline_indicator);
+        self.emit_label_message(label.ordinal, label.message, label.note, column)?;
+        Ok(())
+    }
 
-        write!(self.writer, "{:x$}", "", x = spaces)?;
-        for c in underliner {
-            write!(self.writer, "{}", c.style(style))?;
+    /// Emit the indicators of the active multiline labels in a row that
+    /// continues the previous one.
+    fn emit_multiline_continuation(&mut self) -> std::io::Result<()> {
+        for slot in &self.multiline_slots {
+            match slot {
+                Slot::Active(label) => {
+                    let style = self.indicator_styles[label.ordinal]
+                        .unwrap_or(self.config.styles.multiline_indicator);
+
+                    self.writer
+                        .write_styled(self.config.charset.vertical_bar, style)?;
+                }
+                _ => write!(self.writer, " ")?,
+            }
         }
 
-        writeln!(self.writer, " {}", label.message)?;
         Ok(())
     }
 
+    /// Emit the message of a label, preceded by its number if enabled, and
+    /// followed by its note. `indent` is the width of what comes before the
+    /// message after the multiline indicators, and is used to align the note
+    /// with the message.
+    fn emit_label_message(
+        &mut self,
+        ordinal: usize,
+        message: &str,
+        note: Option<&str>,
+        indent: usize,
+    ) -> std::io::Result<()> {
+        let mut indent = indent + 1;
+        if self.config.number_labels {
+            let number = format!("({})", ordinal + 1);
+            write!(self.writer, " ")?;
+            self.writer
+                .write_styled(&number, self.config.styles.left_column)?;
+
+            indent += number.len() + 1;
+        }
+
+        let message = self.config.transform_message(message);
+        let note = note.map(|note| self.config.transform_message(note));
+
+        // messages might have multiple lines, which are aligned with the first
+        let mut message_lines = message.lines();
+        match message_lines.next() {
+            Some(first) => writeln!(self.writer, " {first}")?,
+            None => writeln!(self.writer)?,
+        }
+        for message_line in message_lines {
+            self.emit_continuation_row(indent, message_line, Style::new())?;
+        }
+
+        for note_line in note.iter().flat_map(|note| note.lines()) {
+            self.emit_continuation_row(indent, note_line, self.config.styles.label_note)?;
+        }
+
+        Ok(())
+    }
+
+    /// Emit a row that continues the message of a label, with the given text
+    /// indented by `indent` after the multiline indicators.
+    fn emit_continuation_row(
+        &mut self,
+        indent: usize,
+        text: &str,
+        style: Style,
+    ) -> std::io::Result<()> {
+        self.emit_left_column(None)?;
+        self.emit_multiline_continuation()?;
+        write!(self.writer, "{:indent$}", "")?;
+        self.writer.write_styled(text, style)?;
+        writeln!(self.writer)
+    }
+
     /// Emits all singleline labels in the current line.
     #[inline]
     fn emit_singleline_labels_in_current(&mut self, line: SourceLine) -> std::io::Result<()> {
@@ -397,16 +1216,84 @@ where
         Ok(())
     }
 
+    /// Emit a row connecting the multiline label in the given slot, which
+    /// starts in the given line, to its first character.
+    fn emit_multiline_connector(
+        &mut self,
+        line: SourceLine,
+        label_slot: usize,
+    ) -> std::io::Result<()> {
+        let Slot::Active(label) = &self.multiline_slots[label_slot] else {
+            return Ok(());
+        };
+
+        let style =
+            self.indicator_styles[label.ordinal].unwrap_or(self.config.styles.multiline_indicator);
+        let start = label.span.start() - line.span.start();
+        let (column, _) = self.underliner_columns(line, SourceSpan::new(start, start));
+
+        self.emit_left_column(None)?;
+        for (index, slot) in self.multiline_slots.iter().enumerate() {
+            let c = match slot {
+                _ if index == label_slot => self.config.charset.connection_vertical_to_right,
+                Slot::Active(_) if index > label_slot => self.config.charset.multiline_crossing,
+                Slot::Active(_) | Slot::RecentlyAdded(_) => self.config.charset.vertical_bar,
+                Slot::Inactive if index > label_slot => self.config.charset.horizontal_bar,
+                Slot::Inactive => ' ',
+            };
+
+            let style = match slot {
+                Slot::Active(other) if index < label_slot => self.indicator_styles[other.ordinal]
+                    .unwrap_or(self.config.styles.multiline_indicator),
+                _ => style,
+            };
+
+            self.writer.write_styled(c, style)?;
+        }
+
+        for _ in 0..column {
+            self.writer
+                .write_styled(self.config.charset.horizontal_bar, style)?;
+        }
+
+        self.writer
+            .write_styled(self.config.charset.underline_start, style)?;
+        writeln!(self.writer)
+    }
+
+    /// Emit the connectors of the multiline labels that start in the
+    /// current line, if enabled.
+    fn emit_multiline_connectors(&mut self, line: SourceLine) -> std::io::Result<()> {
+        if !self.config.multiline_connectors {
+            return Ok(());
+        }
+
+        for label_slot in 0..self.multiline_slots.len() {
+            let starts_here = matches!(
+                &self.multiline_slots[label_slot],
+                Slot::Active(label) if label.line_range.start == self.current_line
+            );
+
+            if starts_here {
+                self.emit_multiline_connector(line, label_slot)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Emit the end of the given multiline label.
     fn emit_multiline_label_end(
         &mut self,
         line: SourceLine,
-        label: MultilineLabel,
+        label: &'a MultilineLabel<'a>,
         label_slot: u32,
     ) -> std::io::Result<()> {
         self.emit_left_column(None)?;
-        let line_width = unicode_width::UnicodeWidthStr::width(line.line);
-        let this_style = label.indicator_style;
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        let line_width =
+            (line_ident_info.len.saturating_sub(self.ident_len)) + self.shown_width(line);
+        let this_style = self.indicator_styles[label.ordinal];
 
         for slot in &self.multiline_slots[..label_slot as usize] {
             match slot {
@@ -414,15 +1301,11 @@ where
                     unreachable!("singleline multiline labels should be impossible")
                 }
                 Slot::Active(label) => {
-                    let style = label
-                        .indicator_style
+                    let style = self.indicator_styles[label.ordinal]
                         .unwrap_or(self.config.styles.multiline_indicator);
 
-                    write!(
-                        self.writer,
-                        "{}",
-                        self.config.charset.vertical_bar.style(style)
-                    )?;
+                    self.writer
+                        .write_styled(self.config.charset.vertical_bar, style)?;
                 }
                 Slot::Inactive => {
                     write!(self.writer, " ")?;
@@ -430,13 +1313,9 @@ where
             }
         }
 
-        write!(
-            self.writer,
-            "{}",
-            self.config
-                .charset
-                .connection_top_to_right
-                .style(this_style.unwrap_or(self.config.styles.multiline_indicator))
+        self.writer.write_styled(
+            self.config.charset.connection_top_to_right,
+            this_style.unwrap_or(self.config.styles.multiline_indicator),
         )?;
 
         for slot in &self.multiline_slots[label_slot as usize + 1..] {
@@ -446,41 +1325,31 @@ where
                 }
                 Slot::Active(label) => {
                     let style = this_style.unwrap_or(
-                        label
-                            .indicator_style
+                        self.indicator_styles[label.ordinal]
                             .unwrap_or(self.config.styles.multiline_indicator),
                     );
 
-                    write!(
-                        self.writer,
-                        "{}",
-                        self.config.charset.multiline_crossing.style(style)
-                    )?;
+                    self.writer
+                        .write_styled(self.config.charset.multiline_crossing, style)?;
                 }
                 Slot::Inactive => {
-                    write!(
-                        self.writer,
-                        "{}",
-                        self.config
-                            .charset
-                            .horizontal_bar
-                            .style(this_style.unwrap_or(self.config.styles.multiline_indicator))
+                    self.writer.write_styled(
+                        self.config.charset.horizontal_bar,
+                        this_style.unwrap_or(self.config.styles.multiline_indicator),
                     )?;
                 }
             }
         }
 
-        let underliner: std::iter::Take<std::iter::Repeat<char>> =
-            std::iter::repeat(self.config.charset.horizontal_bar).take(line_width + 1);
+        let underliner = std::iter::repeat_n(self.config.charset.horizontal_bar, line_width + 1);
         for c in underliner {
-            write!(
-                self.writer,
-                "{}",
-                c.style(this_style.unwrap_or(self.config.styles.multiline_indicator))
+            self.writer.write_styled(
+                c,
+                this_style.unwrap_or(self.config.styles.multiline_indicator),
             )?;
         }
 
-        writeln!(self.writer, " {}", label.message)?;
+        self.emit_label_message(label.ordinal, label.message, label.note, line_width + 1)?;
         Ok(())
     }
 
@@ -489,9 +1358,9 @@ where
         let mut multiline_slot = 0;
         while multiline_slot < self.multiline_slots.len() {
             let Slot::Active(label) = &self.multiline_slots[multiline_slot] else {
-                    multiline_slot += 1;
-                    continue;
-                };
+                multiline_slot += 1;
+                continue;
+            };
 
             if label.line_range.end == self.current_line + 1 {
                 let label =
@@ -515,14 +1384,203 @@ where
         }
     }
 
+    /// Emit a row with only the left column, without any indicators.
+    fn emit_gutter_row(&mut self) -> std::io::Result<()> {
+        write!(self.writer, "{:padding$} ", "", padding = self.left_padding)?;
+        self.emit_gutter(self.config.charset.separator)?;
+        writeln!(self.writer)
+    }
+
+    /// Returns a ruler marking every fifth display column of the given cells,
+    /// where each cell is the column it's under, if any.
+    fn ruler(cells: &[Option<usize>]) -> String {
+        let mut ruler: Vec<_> = cells
+            .iter()
+            .map(|cell| if cell.is_some() { '.' } else { ' ' })
+            .collect();
+
+        for (offset, cell) in cells.iter().enumerate() {
+            let Some(column) = cell.filter(|column| column % 5 == 0) else {
+                continue;
+            };
+
+            let number = column.to_string();
+            if offset + number.len() <= cells.len() {
+                ruler.splice(offset..offset + number.len(), number.chars());
+            }
+        }
+
+        ruler.into_iter().collect()
+    }
+
+    /// Emit a ruler marking every fifth display column of the source, as
+    /// wide as the widest line shown that isn't cut to a window.
+    fn emit_ruler(&mut self) -> std::io::Result<()> {
+        let width = self
+            .shown_lines()
+            .into_iter()
+            .filter_map(|index| Some((index, self.source.line(index)?)))
+            .filter(|&(index, line)| self.line_window(line, index).is_none())
+            .map(|(_, line)| {
+                let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+                line_ident_info.len.saturating_sub(self.ident_len)
+                    + self.display_width(line, line_ident_info.end..line.line.len())
+            })
+            .max();
+
+        // every line shown is cut, so each gets a ruler of its own instead
+        let Some(width) = width else {
+            return Ok(());
+        };
+
+        // columns start after the indentation that was trimmed
+        let cells: Vec<_> = (self.ident_len..self.ident_len + width).map(Some).collect();
+
+        self.emit_left_column(None)?;
+        self.emit_multiline_indicators()?;
+
+        write!(self.writer, " ")?;
+        self.writer
+            .write_styled(Self::ruler(&cells), self.config.styles.left_column)?;
+        writeln!(self.writer)
+    }
+
+    /// Emit a ruler for the given line, which is cut to the given window,
+    /// right above it. The columns of the window are numbered as they are in
+    /// the whole line, skipping the leading ellipsis.
+    fn emit_window_ruler(&mut self, line: SourceLine, window: Range<usize>) -> std::io::Result<()> {
+        let line_ident_info = ident_info(line.line, self.config.indent_tab_width());
+        let window_column =
+            line_ident_info.len + self.display_width(line, line_ident_info.end..window.start);
+
+        let indentation = (self.ident_len.min(line_ident_info.len)..line_ident_info.len).map(Some);
+        let ellipsis = (window.start > line_ident_info.end).then_some(None);
+        let shown = (window_column..window_column + self.display_width(line, window)).map(Some);
+        let cells: Vec<_> = indentation.chain(ellipsis).chain(shown).collect();
+
+        // the source line below starts new multiline labels, not this row
+        self.emit_left_column(None)?;
+        self.emit_multiline_continuation()?;
+
+        write!(self.writer, " ")?;
+        self.writer
+            .write_styled(Self::ruler(&cells), self.config.styles.left_column)?;
+        writeln!(self.writer)
+    }
+
+    /// Returns the indices of the lines shown, in order.
+    pub(crate) fn shown_lines(&self) -> Vec<u32> {
+        let singleline_lines = self.singleline_labels.iter().map(|label| label.line);
+        let multiline_lines = self
+            .multiline_labels
+            .iter()
+            .flat_map(|label| label.line_range.clone());
+
+        let mut lines: Vec<_> = singleline_lines.chain(multiline_lines).collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+
+    /// Returns where the indicators of the labels shown are placed on their
+    /// lines, by line index. The columns are the ones the indicators are
+    /// rendered at, counting from the start of the text of the line.
+    pub(crate) fn indicators(&mut self) -> Vec<(u32, IndicatorLayout)> {
+        let mut indicators = Vec::new();
+        for label in self.singleline_labels.clone() {
+            let line = self.source.line(label.line).unwrap();
+            self.line_window = self.line_window(line, label.line);
+
+            for (start, width) in self.underliners(line, label) {
+                indicators.push((
+                    label.line,
+                    IndicatorLayout {
+                        label: label.ordinal,
+                        kind: IndicatorKind::Underline,
+                        columns: start..start + width,
+                    },
+                ));
+            }
+        }
+
+        for label in self.multiline_labels.clone() {
+            let first = label.line_range.start;
+            let line = self.source.line(first).unwrap();
+            self.line_window = self.line_window(line, first);
+
+            let start = label.span.start() - line.span.start();
+            let (column, width) = self.underliner_columns(line, SourceSpan::new(start, start));
+            indicators.push((
+                first,
+                IndicatorLayout {
+                    label: label.ordinal,
+                    kind: IndicatorKind::MultilineStart,
+                    columns: column..column + width,
+                },
+            ));
+
+            // the end is marked at the last grapheme of the label
+            let last = label.line_range.end - 1;
+            let line = self.source.line(last).unwrap();
+            self.line_window = self.line_window(line, last);
+
+            let end = ((label.span.end() - line.span.start()) as usize).min(line.line.len());
+            let start = line.line[..end]
+                .grapheme_indices(true)
+                .next_back()
+                .map_or(0, |(index, _)| index);
+            let (column, width) =
+                self.underliner_columns(line, SourceSpan::new(start as u32, end as u32));
+            indicators.push((
+                last,
+                IndicatorLayout {
+                    label: label.ordinal,
+                    kind: IndicatorKind::MultilineEnd,
+                    columns: column..column + width,
+                },
+            ));
+        }
+
+        self.line_window = None;
+        indicators
+    }
+
+    /// Returns the length of the indentation trimmed from the lines shown.
+    #[inline]
+    pub(crate) fn ident_len(&self) -> usize {
+        self.ident_len
+    }
+
+    /// Returns the number of slots for multiline labels.
+    #[inline]
+    pub(crate) fn slots_needed(&self) -> usize {
+        self.multiline_slots.len()
+    }
+
     pub(crate) fn write(mut self) -> std::io::Result<()> {
-        // sort singleline labels from biggest to smallest
-        self.singleline_labels
-            .sort_unstable_by_key(|x| std::cmp::Reverse(x.line_span.len()));
+        if self.config.header_gutter_row {
+            self.emit_gutter_row()?;
+        }
 
-        // sort multiline labels from bottom to top (relative to the end)
-        self.multiline_labels
-            .sort_unstable_by_key(|x| std::cmp::Reverse(x.line_range.end));
+        if self.config.show_ruler {
+            self.emit_ruler()?;
+        }
+
+        // sort singleline labels by order, then from biggest to smallest
+        self.singleline_labels
+            .sort_unstable_by_key(|x| (x.order, std::cmp::Reverse(x.line_span.len())));
+
+        // sort multiline labels from bottom to top (relative to the end), so
+        // that the ones that end last get the outermost slots. ties are
+        // broken by their spans, so that the slots they get don't depend on
+        // the order the labels were added in.
+        self.multiline_labels.sort_by_key(|x| {
+            (
+                std::cmp::Reverse(x.line_range.end),
+                std::cmp::Reverse(x.span.end()),
+                x.span.start(),
+            )
+        });
 
         loop {
             if !self.has_active_multiline_labels() {
@@ -535,10 +1593,11 @@ where
                         let line_index = label.line;
                         self.current_line = line_index;
 
+                        // put it back so it's accounted for when emitting the line
+                        self.singleline_labels.insert(0, label);
+
                         let line = self.source.line(line_index).unwrap();
                         self.emit_source_line(line, line_index)?;
-                        self.emit_singleline_label(line, label)?;
-
                         self.emit_singleline_labels_in_current(line)?;
                     }
                     Either::Right(label) => {
@@ -554,6 +1613,7 @@ where
 
                 let line = self.source.line(self.current_line).unwrap();
                 self.emit_source_line(line, self.current_line)?;
+                self.emit_multiline_connectors(line)?;
 
                 self.emit_singleline_labels_in_current(line)?;
                 self.try_finishing_active_multiline_labels()?;
@@ -562,6 +1622,34 @@ where
             }
         }
 
-        Ok(())
+        if self.hidden_labels > 0 {
+            self.emit_left_column(None)?;
+            self.writer
+                .write_styled(self.config.charset.ellipsis, self.config.styles.left_column)?;
+            let localization = &self.config.localization;
+            writeln!(
+                self.writer,
+                " {} {} {}",
+                localization.and,
+                self.hidden_labels,
+                if self.hidden_labels == 1 {
+                    &localization.more_label
+                } else {
+                    &localization.more_labels
+                }
+            )?;
+        }
+
+        if self.config.footer_gutter_row {
+            self.emit_gutter_row()?;
+        }
+
+        let bottom = self
+            .config
+            .charset
+            .gutter_bottom
+            .map(|bottom| (bottom, self.config.styles.left_column));
+
+        self.writer.finish(bottom)
     }
 }
@@ -1,4 +1,7 @@
+use super::{FootnoteKind, Severity};
+use crate::text::{WidthOptions, WidthOverride, TAB_WIDTH};
 use owo_colors::Style;
+use std::borrow::Cow;
 
 /// The charset to use when rendering a diagnostic.
 #[derive(Debug, Clone)]
@@ -7,8 +10,11 @@ pub struct Charset {
     pub vertical_bar: char,
     /// An horizontal bar.
     pub horizontal_bar: char,
-    /// The character used to underline the source
-    /// in single-line labels.
+    /// The character used to underline the start of
+    /// the source in single-line labels.
+    pub underline_start: char,
+    /// The character used to underline the rest of
+    /// the source in single-line labels.
     pub underliner: char,
     /// The character that's used instead of the vertical
     /// bar when not in a source line.
@@ -16,12 +22,30 @@ pub struct Charset {
     /// The character that connects the vertical bar
     /// to the connector in multiline labels.
     pub connection_top_to_right: char,
+    /// The character that branches the connector of a multiline label off
+    /// of its vertical bar. See [`Config::multiline_connectors`].
+    pub connection_vertical_to_right: char,
     /// The character for when a multiline label starts.
     pub multiline_start: char,
     /// The character for when a multiline label ends.
     pub multiline_end: char,
     /// The character for when two multiline labels cross.
     pub multiline_crossing: char,
+    /// The character used to indicate that part of a line
+    /// is not being shown.
+    pub ellipsis: char,
+    /// The character that indicates a note footnote.
+    pub note_indicator: char,
+    /// The character that indicates a help footnote.
+    pub help_indicator: char,
+    /// The character that indicates an info footnote.
+    pub info_indicator: char,
+    /// The character used in the left column of the first row of the body,
+    /// if any.
+    pub gutter_top: Option<char>,
+    /// The character used in the left column of the last row of the body,
+    /// if any.
+    pub gutter_bottom: Option<char>,
 }
 
 impl Default for Charset {
@@ -29,25 +53,88 @@ impl Default for Charset {
         Self {
             vertical_bar: '│',
             horizontal_bar: '╶',
+            underline_start: '^',
             underliner: '^',
             separator: ':',
             connection_top_to_right: '╰',
+            connection_vertical_to_right: '├',
             multiline_start: '┬',
             multiline_end: '┼',
             multiline_crossing: '┼',
+            ellipsis: '…',
+            note_indicator: '>',
+            help_indicator: '?',
+            info_indicator: 'i',
+            gutter_top: None,
+            gutter_bottom: None,
+        }
+    }
+}
+
+impl Charset {
+    /// A charset that only uses ASCII characters, for terminals and fonts
+    /// that can't render box drawing characters.
+    pub fn ascii() -> Self {
+        Self {
+            vertical_bar: '|',
+            horizontal_bar: '-',
+            underline_start: '^',
+            underliner: '^',
+            separator: ':',
+            connection_top_to_right: '`',
+            connection_vertical_to_right: '|',
+            multiline_start: ',',
+            multiline_end: '+',
+            multiline_crossing: '+',
+            ellipsis: '~',
+            note_indicator: '>',
+            help_indicator: '?',
+            info_indicator: 'i',
+            gutter_top: None,
+            gutter_bottom: None,
+        }
+    }
+
+    /// The default charset, but with the gutter of the body closed by
+    /// corners, giving it a boxed look.
+    pub fn boxed() -> Self {
+        Self {
+            gutter_top: Some('╭'),
+            gutter_bottom: Some('╰'),
+            ..Default::default()
+        }
+    }
+
+    /// The default charset, but with single-line labels underlined
+    /// like `^~~~`.
+    pub fn squiggly() -> Self {
+        Self {
+            underliner: '~',
+            ..Default::default()
         }
     }
 }
 
 /// Default styles to use for each part of a diagnostic.
+///
+/// Besides the default ones, there are a few presets to choose from. The
+/// severity of a diagnostic is shown with the style for it.
 #[derive(Debug, Clone)]
 pub struct DefaultStyles {
     pub source_name: Style,
     pub source: Style,
+    pub highlighted_source: Style,
     pub left_column: Style,
     pub multiline_indicator: Style,
     pub singleline_indicator: Style,
+    pub primary_indicator: Style,
     pub footnote_indicator: Style,
+    pub help_indicator: Style,
+    pub info_indicator: Style,
+    pub label_note: Style,
+    pub error: Style,
+    pub warning: Style,
+    pub note: Style,
 }
 
 impl Default for DefaultStyles {
@@ -55,17 +142,1098 @@ impl Default for DefaultStyles {
         Self {
             source_name: Style::new().white().bold(),
             source: Style::new().white(),
+            highlighted_source: Style::new().yellow(),
             left_column: Style::new().bright_blue().bold(),
             multiline_indicator: Style::new().yellow(),
             singleline_indicator: Style::new().yellow(),
+            primary_indicator: Style::new().bright_red().bold(),
             footnote_indicator: Style::new().bright_blue().bold(),
+            help_indicator: Style::new().bright_green().bold(),
+            info_indicator: Style::new().bright_cyan().bold(),
+            label_note: Style::new().dimmed(),
+            error: Style::new().bright_red().bold(),
+            warning: Style::new().yellow(),
+            note: Style::new().bright_blue().bold(),
+        }
+    }
+}
+
+impl DefaultStyles {
+    /// Styles that apply no formatting at all.
+    pub fn plain() -> Self {
+        Self {
+            source_name: Style::new(),
+            source: Style::new(),
+            highlighted_source: Style::new(),
+            left_column: Style::new(),
+            multiline_indicator: Style::new(),
+            singleline_indicator: Style::new(),
+            primary_indicator: Style::new(),
+            footnote_indicator: Style::new(),
+            help_indicator: Style::new(),
+            info_indicator: Style::new(),
+            label_note: Style::new(),
+            error: Style::new(),
+            warning: Style::new(),
+            note: Style::new(),
+        }
+    }
+
+    /// Styles without any colors, using only bold and underline to
+    /// distinguish the parts of a diagnostic.
+    pub fn monochrome() -> Self {
+        Self {
+            source_name: Style::new().bold(),
+            source: Style::new(),
+            highlighted_source: Style::new().underline(),
+            left_column: Style::new().bold(),
+            multiline_indicator: Style::new().bold(),
+            singleline_indicator: Style::new().bold(),
+            primary_indicator: Style::new().bold().underline(),
+            footnote_indicator: Style::new().bold(),
+            help_indicator: Style::new().bold(),
+            info_indicator: Style::new().bold(),
+            label_note: Style::new().dimmed(),
+            error: Style::new().bold().underline(),
+            warning: Style::new().bold(),
+            note: Style::new().bold(),
+        }
+    }
+
+    /// Styles using only bright colors, for better legibility.
+    pub fn high_contrast() -> Self {
+        Self {
+            source_name: Style::new().bright_white().bold(),
+            source: Style::new().bright_white(),
+            highlighted_source: Style::new().bright_yellow().bold(),
+            left_column: Style::new().bright_cyan().bold(),
+            multiline_indicator: Style::new().bright_yellow().bold(),
+            singleline_indicator: Style::new().bright_yellow().bold(),
+            primary_indicator: Style::new().bright_red().bold(),
+            footnote_indicator: Style::new().bright_cyan().bold(),
+            help_indicator: Style::new().bright_green().bold(),
+            info_indicator: Style::new().bright_magenta().bold(),
+            label_note: Style::new().white(),
+            error: Style::new().bright_red().bold(),
+            warning: Style::new().bright_yellow().bold(),
+            note: Style::new().bright_cyan().bold(),
+        }
+    }
+
+    /// The default styles, but with the indicators of labels colored after
+    /// the given severity: red for errors, yellow for warnings and blue for
+    /// notes. Labels with their own style still use it.
+    pub fn for_severity(severity: Severity) -> Self {
+        let indicator = match severity {
+            Severity::Note => Style::new().bright_blue(),
+            Severity::Warning => Style::new().yellow(),
+            Severity::Error => Style::new().red(),
+        };
+
+        Self {
+            multiline_indicator: indicator,
+            singleline_indicator: indicator,
+            ..Default::default()
+        }
+    }
+
+    /// Styles with the colors of the default ones, but dimmed so that
+    /// diagnostics are less prominent.
+    pub fn dimmed() -> Self {
+        Self {
+            source_name: Style::new().white().bold(),
+            source: Style::new().white().dimmed(),
+            highlighted_source: Style::new().yellow(),
+            left_column: Style::new().blue().dimmed(),
+            multiline_indicator: Style::new().yellow().dimmed(),
+            singleline_indicator: Style::new().yellow().dimmed(),
+            primary_indicator: Style::new().red(),
+            footnote_indicator: Style::new().blue().dimmed(),
+            help_indicator: Style::new().green().dimmed(),
+            info_indicator: Style::new().cyan().dimmed(),
+            label_note: Style::new().dimmed(),
+            error: Style::new().red(),
+            warning: Style::new().yellow().dimmed(),
+            note: Style::new().blue().dimmed(),
+        }
+    }
+}
+
+/// Whether to render diagnostics with colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Use colors in the modes meant to be read by humans, but not in the
+    /// ones meant to be consumed by tools (such as the oneline mode).
+    ///
+    /// This doesn't check whether the output is a terminal: callers writing
+    /// to pipes or files should choose [`ColorChoice::Never`] themselves.
+    #[default]
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors. This also ignores the styles of labels and sources.
+    Never,
+}
+
+/// Whether to render parts of diagnostics as terminal hyperlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HyperlinkChoice {
+    /// Use hyperlinks if the terminal seems to support them.
+    #[default]
+    Auto,
+    /// Always use hyperlinks.
+    Always,
+    /// Never use hyperlinks.
+    Never,
+}
+
+/// The alignment of the line numbers in the left column. Rows without a line
+/// number are the same width either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineNumberAlign {
+    /// Line numbers are aligned to the left.
+    Left,
+    /// Line numbers are aligned to the right.
+    #[default]
+    Right,
+}
+
+/// How the indentation of lines is measured when dedenting them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedentMode {
+    /// Indentation is measured by its display width, so a tab counts as
+    /// [`Config::tab_width`] spaces.
+    #[default]
+    DisplayWidth,
+    /// Indentation is measured by the number of whitespace characters in it,
+    /// so a tab counts as a single space.
+    CharCount,
+}
+
+/// How carriage returns that don't end a line are displayed. Writing them as
+/// they are would move the cursor of the terminal to the start of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CarriageReturn {
+    /// Carriage returns are not displayed.
+    #[default]
+    Strip,
+    /// Carriage returns are displayed as `\r`.
+    Escape,
+}
+
+/// The style of the header of a diagnostic, which shows the name of its
+/// source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderStyle {
+    /// The source name is shown in brackets, like `@ [main.rs]`.
+    #[default]
+    Bracketed,
+    /// The source name is shown after an arrow, like `--> main.rs`.
+    Arrow,
+}
+
+/// A function that transforms the messages of labels and footnotes before
+/// they're rendered.
+pub type MessageTransform = fn(&str) -> Cow<'_, str>;
+
+/// The words used when rendering a diagnostic, so that they can be
+/// translated.
+///
+/// The row that replaces labels hidden by [`Config::max_labels`] is
+/// translated as well.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    /// The word for a single line, as in `[line 3]`.
+    pub line: Cow<'static, str>,
+    /// The word for multiple lines, as in `[lines 3..=5]`.
+    pub lines: Cow<'static, str>,
+    /// The name of the [`Severity::Error`] severity.
+    pub error: Cow<'static, str>,
+    /// The name of the [`Severity::Warning`] severity.
+    pub warning: Cow<'static, str>,
+    /// The name of the [`Severity::Note`] severity, also used for
+    /// [`FootnoteKind::Note`] footnotes.
+    pub note: Cow<'static, str>,
+    /// The name of [`FootnoteKind::Help`] footnotes.
+    pub help: Cow<'static, str>,
+    /// The name of [`FootnoteKind::Info`] footnotes.
+    pub info: Cow<'static, str>,
+    /// The word used to reference a label from a footnote, as in
+    /// `note: see (1)`.
+    pub see: Cow<'static, str>,
+    /// The word before the number of hidden labels, as in
+    /// `… and 2 more labels`.
+    pub and: Cow<'static, str>,
+    /// The words after a single hidden label, as in `… and 1 more label`.
+    pub more_label: Cow<'static, str>,
+    /// The words after multiple hidden labels, as in `… and 2 more labels`.
+    pub more_labels: Cow<'static, str>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            line: Cow::Borrowed("line"),
+            lines: Cow::Borrowed("lines"),
+            error: Cow::Borrowed(Severity::Error.as_str()),
+            warning: Cow::Borrowed(Severity::Warning.as_str()),
+            note: Cow::Borrowed(Severity::Note.as_str()),
+            help: Cow::Borrowed("help"),
+            info: Cow::Borrowed("info"),
+            see: Cow::Borrowed("see"),
+            and: Cow::Borrowed("and"),
+            more_label: Cow::Borrowed("more label"),
+            more_labels: Cow::Borrowed("more labels"),
+        }
+    }
+}
+
+impl Localization {
+    /// Returns the name of the given severity.
+    pub fn severity(&self, severity: Severity) -> &str {
+        match severity {
+            Severity::Error => &self.error,
+            Severity::Warning => &self.warning,
+            Severity::Note => &self.note,
+        }
+    }
+
+    /// Returns the name of the given kind of footnote.
+    pub fn footnote_kind(&self, kind: FootnoteKind) -> &str {
+        match kind {
+            FootnoteKind::Note => &self.note,
+            FootnoteKind::Help => &self.help,
+            FootnoteKind::Info => &self.info,
         }
     }
 }
 
 /// Configuration used to render a diagnostic.
-#[derive(Debug, Clone, Default)]
+///
+/// Configs can be built by tweaking the fields of the default one, with
+/// [`Config::builder`], or with the `with_*` methods, which start from any
+/// config, such as a preset:
+/// ```
+/// # use yumy::{Charset, ColorChoice, Config};
+/// let config = Config::default()
+///     .with_charset(Charset::squiggly())
+///     .with_tab_width(2)
+///     .with_color(ColorChoice::Never);
+///
+/// assert_eq!(config.tab_width, 2);
+/// ```
+#[derive(Debug, Clone)]
 pub struct Config {
     pub charset: Charset,
     pub styles: DefaultStyles,
+    /// Whether to render with colors.
+    pub color: ColorChoice,
+    /// Whether to show the number of each label next to its message. Footnotes
+    /// that reference a label will also show its number.
+    pub number_labels: bool,
+    /// The alignment of the line numbers in the left column.
+    pub line_number_align: LineNumberAlign,
+    /// Function used to override the width of grapheme clusters, for when
+    /// the terminal renders them differently than expected. Returning `None`
+    /// means the default width is used.
+    pub width_override: Option<WidthOverride>,
+    /// Whether characters with an ambiguous East Asian width should be
+    /// considered wide. Enable this when rendering to terminals using a CJK
+    /// locale.
+    pub east_asian_wide: bool,
+    /// The width of a tab, in columns.
+    pub tab_width: usize,
+    /// Whether tabs in the source should be rendered as `tab_width` spaces.
+    /// This guarantees the source is aligned with the label indicators
+    /// regardless of the tab stops of the terminal.
+    pub expand_tabs: bool,
+    /// How carriage returns in the middle of a line are displayed. Either
+    /// way, the indicators stay aligned with the source.
+    pub carriage_return: CarriageReturn,
+    /// Whether control characters in the source (other than tabs and
+    /// carriage returns) should be displayed as visible symbols instead of
+    /// being written as they are. This keeps sources from messing with the
+    /// terminal, such as by writing their own escape sequences.
+    ///
+    /// C0 controls and `DEL` become one column wide control pictures, like
+    /// `␛`, while other controls become escapes like `\x85`. Either way, the
+    /// indicators stay aligned.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unexpected escape sequence")
+    ///     .with_source(Source::new("let a = \x1b[31mred;\u{85}b", None))
+    ///     .with_label(Label::new(8..13, "here"))
+    ///     .with_label(Label::new(13..16, "not found"))
+    ///     .with_label(Label::new(19..20, "after a C1 control"));
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::plain().with_escape_control(true);
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(!rendered.contains('\x1b'));
+    /// assert!(rendered.contains(
+    ///     "1 │  let a = ␛[31mred;\\x85b\n  \
+    ///      :          ^^^^^ here\n  \
+    ///      :               ^^^ not found\n  \
+    ///      :                       ^ after a C1 control\n"
+    /// ));
+    /// ```
+    pub escape_control: bool,
+    /// Whether ANSI escape sequences in the source should be removed, so
+    /// that the source can't change how the diagnostic looks. This takes
+    /// precedence over [`Config::escape_control`].
+    ///
+    /// Sequences are removed as a whole, even when labels start or end inside
+    /// of them, and take no columns.
+    pub strip_ansi: bool,
+    /// Whether source lines containing right-to-left text (such as Arabic or
+    /// Hebrew) should be wrapped in bidi isolates, so that the terminal
+    /// doesn't reorder them relative to the rest of the diagnostic.
+    ///
+    /// Label indicators are placed according to the logical order of the
+    /// source, so they're only guaranteed to line up with left-to-right
+    /// text. This is a best-effort mitigation: terminals that implement the
+    /// bidi algorithm may still reorder the right-to-left runs inside the
+    /// isolate, and terminals that don't are unaffected.
+    pub bidi_isolate: bool,
+    /// Whether the indentation shared by all the lines shown should be
+    /// trimmed. When disabled, lines are shown with their original
+    /// indentation.
+    ///
+    /// Only spaces and tabs count as indentation, so a space carrying a
+    /// combining mark is kept together with it. Labels starting inside the
+    /// indentation underline the part of it that is still shown, if any, and
+    /// blank lines don't count, as they have no text to be aligned.
+    pub dedent: bool,
+    /// How the indentation of lines is measured when dedenting them.
+    pub dedent_mode: DedentMode,
+    /// The maximum width of a source line. Lines wider than this are cut to
+    /// a window around their labels, not counting the ellipses that mark
+    /// where they were cut.
+    ///
+    /// Where multiline labels start and end is kept in the window as well.
+    pub max_line_width: Option<usize>,
+    /// Whether the parts of the source covered by singleline labels should be
+    /// rendered with the style of their label (or the highlighted source
+    /// style, if the label has none).
+    pub highlight_source: bool,
+    /// Whether the header should show the line and column of the primary
+    /// label after the name of the source, like `[main.rs:12:5]`.
+    pub show_position: bool,
+    /// The style of the header.
+    pub header_style: HeaderStyle,
+    /// The name shown for sources without one.
+    pub unknown_source_name: Cow<'static, str>,
+    /// Whether to render parts of the diagnostic, such as its code, as
+    /// clickable hyperlinks. Hyperlinks are never used without colors.
+    pub hyperlinks: HyperlinkChoice,
+    /// The words used when rendering, such as the names of severities.
+    pub localization: Localization,
+    /// Function applied to the messages of labels (and their notes) and
+    /// footnotes when rendering, without changing the stored ones. Useful to
+    /// enforce a consistent style for messages, such as capitalizing them.
+    ///
+    /// The transform is applied before messages are split into lines and
+    /// measured, so their alignment is computed on the transformed text.
+    pub message_transform: Option<MessageTransform>,
+    /// Whether an empty row should be shown between the header and the
+    /// body.
+    pub header_gutter_row: bool,
+    /// Whether an empty row should be shown after the body, before the
+    /// footnotes.
+    pub footer_gutter_row: bool,
+    /// The maximum number of labels to show. Labels are shown in the order
+    /// they appear in the source, and the ones past the limit are replaced by
+    /// a row saying how many were hidden.
+    ///
+    /// A label with [extra spans](super::Label::extra_spans) counts only once:
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: mismatched delimiters")
+    ///     .with_source(Source::new("let v = (1,\n  2];\nlet w = x;", None))
+    ///     .with_label(Label::new(8..9, "a").with_extra_span(15..16))
+    ///     .with_label(Label::new(26..27, "b"));
+    ///
+    /// let mut rendered = String::new();
+    /// let config = Config::plain().with_max_labels(Some(2));
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains("3 │  let w = x;\n  :          ^ b\n"));
+    /// assert!(!rendered.contains("more label"));
+    /// ```
+    pub max_labels: Option<usize>,
+    /// The number of spaces to indent every line of the diagnostic by, so
+    /// that it can be nested inside other output. Empty lines are left
+    /// empty.
+    pub block_indent: usize,
+    /// Whether the diagnostic should end with an empty line, which separates
+    /// it from whatever comes next. Disable it when embedding diagnostics in
+    /// tight layouts.
+    pub trailing_newline: bool,
+    /// Whether spans of labels that go past the end of the source should be
+    /// clamped to it, so that stale spans are rendered on a best-effort
+    /// basis instead of failing. A clamped label is truncated at the end of
+    /// the source, losing whatever part of it was out of bounds.
+    pub clamp_spans: bool,
+    /// The minimum width of the line numbers, in columns. Each diagnostic
+    /// makes room for the widest line number it shows, so setting this to
+    /// the widest of a batch keeps their gutters aligned.
+    pub min_line_number_width: Option<usize>,
+    /// Whether a ruler marking the display columns of the source should be
+    /// shown above the body. The columns are 0-based and account for the
+    /// indentation that was trimmed. Useful when debugging the placement of
+    /// indicators.
+    ///
+    /// Lines cut by [`Config::max_line_width`] get a ruler of their own right
+    /// above them, numbering the columns of the part of the line shown.
+    pub show_ruler: bool,
+    /// The minimum width of the underline of singleline labels, so that
+    /// tiny spans are easier to see. Shorter underlines are widened on both
+    /// sides, as evenly as the bounds of their line and the underlines next
+    /// to them allow.
+    ///
+    /// Underlines sharing a row keep a column between them, so that they
+    /// don't run into each other. Underlines of different labels on the same
+    /// line split the gap between them, so they never overlap either.
+    pub min_underline_width: usize,
+    /// Whether multiline labels should be connected to where they start in
+    /// their first line, with a row below it pointing at the first
+    /// character of the label.
+    pub multiline_connectors: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            charset: Charset::default(),
+            styles: DefaultStyles::default(),
+            color: ColorChoice::default(),
+            number_labels: false,
+            line_number_align: LineNumberAlign::default(),
+            width_override: None,
+            east_asian_wide: false,
+            tab_width: TAB_WIDTH,
+            expand_tabs: false,
+            carriage_return: CarriageReturn::default(),
+            escape_control: false,
+            strip_ansi: false,
+            bidi_isolate: false,
+            dedent: true,
+            dedent_mode: DedentMode::default(),
+            max_line_width: None,
+            highlight_source: false,
+            show_position: false,
+            header_style: HeaderStyle::default(),
+            unknown_source_name: Cow::Borrowed("unknown"),
+            hyperlinks: HyperlinkChoice::default(),
+            localization: Localization::default(),
+            message_transform: None,
+            header_gutter_row: false,
+            footer_gutter_row: false,
+            max_labels: None,
+            block_indent: 0,
+            trailing_newline: true,
+            clamp_spans: false,
+            min_line_number_width: None,
+            show_ruler: false,
+            min_underline_width: 1,
+            multiline_connectors: false,
+        }
+    }
+}
+
+impl Config {
+    /// A config that never uses colors nor any other escape sequences, so
+    /// that rendering with it yields raw text. Useful for tests and logs.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let x = y;", Some("main.rs")))
+    ///     .with_label(Label::new(8..9, "not found"));
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// assert!(!rendered.contains('\x1b'));
+    /// assert!(rendered.contains("^ not found"));
+    /// ```
+    pub fn plain() -> Self {
+        Self {
+            styles: DefaultStyles::plain(),
+            color: ColorChoice::Never,
+            hyperlinks: HyperlinkChoice::Never,
+            ..Default::default()
+        }
+    }
+
+    /// A config that only uses ASCII characters and never uses colors.
+    pub fn ascii_plain() -> Self {
+        Self {
+            charset: Charset::ascii(),
+            styles: DefaultStyles::plain(),
+            color: ColorChoice::Never,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a [`ConfigBuilder`] starting from the default config.
+    #[inline(always)]
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Sets the charset.
+    #[inline(always)]
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Sets the default styles.
+    #[inline(always)]
+    pub fn with_styles(mut self, styles: DefaultStyles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// Sets whether to render with colors.
+    #[inline(always)]
+    pub fn with_color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets whether to number labels. See [`Config::number_labels`].
+    #[inline(always)]
+    pub fn with_number_labels(mut self, number_labels: bool) -> Self {
+        self.number_labels = number_labels;
+        self
+    }
+
+    /// Sets the alignment of the line numbers.
+    #[inline(always)]
+    pub fn with_line_number_align(mut self, align: LineNumberAlign) -> Self {
+        self.line_number_align = align;
+        self
+    }
+
+    /// Sets the function used to override the width of grapheme clusters.
+    #[inline(always)]
+    pub fn with_width_override(mut self, width_override: WidthOverride) -> Self {
+        self.width_override = Some(width_override);
+        self
+    }
+
+    /// Sets whether ambiguous East Asian characters are wide.
+    #[inline(always)]
+    pub fn with_east_asian_wide(mut self, east_asian_wide: bool) -> Self {
+        self.east_asian_wide = east_asian_wide;
+        self
+    }
+
+    /// Sets the width of a tab, in columns.
+    #[inline(always)]
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Sets whether tabs should be expanded into spaces.
+    #[inline(always)]
+    pub fn with_expand_tabs(mut self, expand_tabs: bool) -> Self {
+        self.expand_tabs = expand_tabs;
+        self
+    }
+
+    /// Sets how carriage returns in the middle of a line are displayed.
+    #[inline(always)]
+    pub fn with_carriage_return(mut self, carriage_return: CarriageReturn) -> Self {
+        self.carriage_return = carriage_return;
+        self
+    }
+
+    /// Sets whether control characters in the source should be displayed as
+    /// visible symbols.
+    #[inline(always)]
+    pub fn with_escape_control(mut self, escape_control: bool) -> Self {
+        self.escape_control = escape_control;
+        self
+    }
+
+    /// Sets whether ANSI escape sequences in the source should be removed.
+    #[inline(always)]
+    pub fn with_strip_ansi(mut self, strip_ansi: bool) -> Self {
+        self.strip_ansi = strip_ansi;
+        self
+    }
+
+    /// Sets whether source lines with right-to-left text should be wrapped
+    /// in bidi isolates.
+    #[inline(always)]
+    pub fn with_bidi_isolate(mut self, bidi_isolate: bool) -> Self {
+        self.bidi_isolate = bidi_isolate;
+        self
+    }
+
+    /// Sets whether shared indentation should be trimmed.
+    #[inline(always)]
+    pub fn with_dedent(mut self, dedent: bool) -> Self {
+        self.dedent = dedent;
+        self
+    }
+
+    /// Sets how the indentation of lines is measured when dedenting them.
+    #[inline(always)]
+    pub fn with_dedent_mode(mut self, dedent_mode: DedentMode) -> Self {
+        self.dedent_mode = dedent_mode;
+        self
+    }
+
+    /// Sets the maximum width of a source line, or `None` to never cut
+    /// lines.
+    #[inline(always)]
+    pub fn with_max_line_width(mut self, max_line_width: Option<usize>) -> Self {
+        self.max_line_width = max_line_width;
+        self
+    }
+
+    /// Sets whether labeled source text should be highlighted.
+    #[inline(always)]
+    pub fn with_highlight_source(mut self, highlight_source: bool) -> Self {
+        self.highlight_source = highlight_source;
+        self
+    }
+
+    /// Sets whether the header should show the position of the primary label.
+    #[inline(always)]
+    pub fn with_show_position(mut self, show_position: bool) -> Self {
+        self.show_position = show_position;
+        self
+    }
+
+    /// Sets the style of the header.
+    #[inline(always)]
+    pub fn with_header_style(mut self, header_style: HeaderStyle) -> Self {
+        self.header_style = header_style;
+        self
+    }
+
+    /// Sets the name shown for sources without one.
+    #[inline(always)]
+    pub fn with_unknown_source_name<N>(mut self, unknown_source_name: N) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        self.unknown_source_name = unknown_source_name.into();
+        self
+    }
+
+    /// Sets whether to render hyperlinks.
+    #[inline(always)]
+    pub fn with_hyperlinks(mut self, hyperlinks: HyperlinkChoice) -> Self {
+        self.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// Sets the words used when rendering.
+    #[inline(always)]
+    pub fn with_localization(mut self, localization: Localization) -> Self {
+        self.localization = localization;
+        self
+    }
+
+    /// Sets the function applied to the messages of labels and footnotes.
+    #[inline(always)]
+    pub fn with_message_transform(mut self, message_transform: MessageTransform) -> Self {
+        self.message_transform = Some(message_transform);
+        self
+    }
+
+    /// Sets whether an empty row should be shown between the header and the
+    /// body.
+    #[inline(always)]
+    pub fn with_header_gutter_row(mut self, header_gutter_row: bool) -> Self {
+        self.header_gutter_row = header_gutter_row;
+        self
+    }
+
+    /// Sets whether an empty row should be shown after the body, before the
+    /// footnotes.
+    #[inline(always)]
+    pub fn with_footer_gutter_row(mut self, footer_gutter_row: bool) -> Self {
+        self.footer_gutter_row = footer_gutter_row;
+        self
+    }
+
+    /// Sets the maximum number of labels to show, or `None` to show all of
+    /// them.
+    #[inline(always)]
+    pub fn with_max_labels(mut self, max_labels: Option<usize>) -> Self {
+        self.max_labels = max_labels;
+        self
+    }
+
+    /// Sets the number of spaces to indent every line of the diagnostic by.
+    #[inline(always)]
+    pub fn with_block_indent(mut self, block_indent: usize) -> Self {
+        self.block_indent = block_indent;
+        self
+    }
+
+    /// Sets whether the diagnostic should end with an empty line. See
+    /// [`Config::trailing_newline`].
+    #[inline(always)]
+    pub fn with_trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Sets whether out of bounds spans should be clamped to the source. See
+    /// [`Config::clamp_spans`].
+    #[inline(always)]
+    pub fn with_clamp_spans(mut self, clamp_spans: bool) -> Self {
+        self.clamp_spans = clamp_spans;
+        self
+    }
+
+    /// Sets the minimum width of the line numbers, or `None` to size them to
+    /// the diagnostic alone. See [`Config::min_line_number_width`].
+    #[inline(always)]
+    pub fn with_min_line_number_width(mut self, width: Option<usize>) -> Self {
+        self.min_line_number_width = width;
+        self
+    }
+
+    /// Sets whether a ruler should be shown above the body. See
+    /// [`Config::show_ruler`].
+    #[inline(always)]
+    pub fn with_show_ruler(mut self, show_ruler: bool) -> Self {
+        self.show_ruler = show_ruler;
+        self
+    }
+
+    /// Sets the minimum width of the underline of singleline labels. See
+    /// [`Config::min_underline_width`].
+    #[inline(always)]
+    pub fn with_min_underline_width(mut self, width: usize) -> Self {
+        self.min_underline_width = width;
+        self
+    }
+
+    /// Sets whether multiline labels should be connected to where they
+    /// start. See [`Config::multiline_connectors`].
+    #[inline(always)]
+    pub fn with_multiline_connectors(mut self, multiline_connectors: bool) -> Self {
+        self.multiline_connectors = multiline_connectors;
+        self
+    }
+
+    /// Returns this config with [`ColorChoice::Auto`] resolved to either
+    /// [`ColorChoice::Always`] or [`ColorChoice::Never`], depending on
+    /// whether the rendering mode uses colors by default. If the result
+    /// doesn't use colors, the default styles are replaced by plain ones.
+    pub(crate) fn resolve_colors(&self, colored_by_default: bool) -> Cow<'_, Config> {
+        let colored = match self.color {
+            ColorChoice::Auto => colored_by_default,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+
+        if colored {
+            return Cow::Borrowed(self);
+        }
+
+        Cow::Owned(Config {
+            styles: DefaultStyles::plain(),
+            color: ColorChoice::Never,
+            ..self.clone()
+        })
+    }
+
+    /// Returns whether hyperlinks should be used with this config. With
+    /// [`HyperlinkChoice::Auto`], the environment is checked for terminals
+    /// known to support them.
+    pub(crate) fn use_hyperlinks(&self) -> bool {
+        if self.color == ColorChoice::Never {
+            return false;
+        }
+
+        match self.hyperlinks {
+            HyperlinkChoice::Always => true,
+            HyperlinkChoice::Never => false,
+            HyperlinkChoice::Auto => {
+                let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+                let known_program = var("TERM_PROGRAM").is_some_and(|program| {
+                    matches!(
+                        program.as_str(),
+                        "iTerm.app" | "WezTerm" | "vscode" | "ghostty"
+                    )
+                });
+                let recent_vte = var("VTE_VERSION")
+                    .and_then(|version| version.parse::<u32>().ok())
+                    .is_some_and(|version| version >= 5000);
+
+                known_program
+                    || recent_vte
+                    || var("WT_SESSION").is_some()
+                    || var("KITTY_WINDOW_ID").is_some()
+                    || var("DOMTERM").is_some()
+            }
+        }
+    }
+
+    /// Applies the message transform of this config, if any, to the given
+    /// message.
+    #[inline]
+    pub(crate) fn transform_message<'m>(&self, message: &'m str) -> Cow<'m, str> {
+        match self.message_transform {
+            Some(transform) => transform(message),
+            None => Cow::Borrowed(message),
+        }
+    }
+
+    /// The width of a tab when measuring indentation with this config.
+    #[inline]
+    pub(crate) fn indent_tab_width(&self) -> usize {
+        match self.dedent_mode {
+            DedentMode::DisplayWidth => self.tab_width,
+            DedentMode::CharCount => 1,
+        }
+    }
+
+    /// The options used to measure the width of text with this config.
+    #[inline]
+    pub(crate) fn width_options(&self) -> WidthOptions {
+        WidthOptions {
+            width_override: self.width_override,
+            east_asian_wide: self.east_asian_wide,
+            tab_width: self.tab_width,
+        }
+    }
+}
+
+/// A fluent builder for [`Config`], created with [`Config::builder`]. It
+/// starts from the default config, and has a setter for each of the `with_*`
+/// methods of [`Config`].
+///
+/// ```
+/// # use yumy::{Charset, ColorChoice, Config};
+/// let config = Config::builder()
+///     .charset(Charset::ascii())
+///     .tab_width(2)
+///     .color(ColorChoice::Never)
+///     .build();
+///
+/// assert_eq!(config.tab_width, 2);
+/// assert_eq!(config.color, ColorChoice::Never);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder(Config);
+
+impl ConfigBuilder {
+    /// See [`Config::with_charset`].
+    #[inline(always)]
+    pub fn charset(self, charset: Charset) -> Self {
+        Self(self.0.with_charset(charset))
+    }
+
+    /// See [`Config::with_styles`].
+    #[inline(always)]
+    pub fn styles(self, styles: DefaultStyles) -> Self {
+        Self(self.0.with_styles(styles))
+    }
+
+    /// See [`Config::with_color`].
+    #[inline(always)]
+    pub fn color(self, color: ColorChoice) -> Self {
+        Self(self.0.with_color(color))
+    }
+
+    /// See [`Config::with_number_labels`].
+    #[inline(always)]
+    pub fn number_labels(self, number_labels: bool) -> Self {
+        Self(self.0.with_number_labels(number_labels))
+    }
+
+    /// See [`Config::with_line_number_align`].
+    #[inline(always)]
+    pub fn line_number_align(self, align: LineNumberAlign) -> Self {
+        Self(self.0.with_line_number_align(align))
+    }
+
+    /// See [`Config::with_width_override`].
+    #[inline(always)]
+    pub fn width_override(self, width_override: WidthOverride) -> Self {
+        Self(self.0.with_width_override(width_override))
+    }
+
+    /// See [`Config::with_east_asian_wide`].
+    #[inline(always)]
+    pub fn east_asian_wide(self, east_asian_wide: bool) -> Self {
+        Self(self.0.with_east_asian_wide(east_asian_wide))
+    }
+
+    /// See [`Config::with_tab_width`].
+    #[inline(always)]
+    pub fn tab_width(self, tab_width: usize) -> Self {
+        Self(self.0.with_tab_width(tab_width))
+    }
+
+    /// See [`Config::with_expand_tabs`].
+    #[inline(always)]
+    pub fn expand_tabs(self, expand_tabs: bool) -> Self {
+        Self(self.0.with_expand_tabs(expand_tabs))
+    }
+
+    /// See [`Config::with_carriage_return`].
+    #[inline(always)]
+    pub fn carriage_return(self, carriage_return: CarriageReturn) -> Self {
+        Self(self.0.with_carriage_return(carriage_return))
+    }
+
+    /// See [`Config::with_escape_control`].
+    #[inline(always)]
+    pub fn escape_control(self, escape_control: bool) -> Self {
+        Self(self.0.with_escape_control(escape_control))
+    }
+
+    /// See [`Config::with_strip_ansi`].
+    #[inline(always)]
+    pub fn strip_ansi(self, strip_ansi: bool) -> Self {
+        Self(self.0.with_strip_ansi(strip_ansi))
+    }
+
+    /// See [`Config::with_bidi_isolate`].
+    #[inline(always)]
+    pub fn bidi_isolate(self, bidi_isolate: bool) -> Self {
+        Self(self.0.with_bidi_isolate(bidi_isolate))
+    }
+
+    /// See [`Config::with_dedent`].
+    #[inline(always)]
+    pub fn dedent(self, dedent: bool) -> Self {
+        Self(self.0.with_dedent(dedent))
+    }
+
+    /// See [`Config::with_dedent_mode`].
+    #[inline(always)]
+    pub fn dedent_mode(self, dedent_mode: DedentMode) -> Self {
+        Self(self.0.with_dedent_mode(dedent_mode))
+    }
+
+    /// See [`Config::with_max_line_width`].
+    #[inline(always)]
+    pub fn max_line_width(self, max_line_width: Option<usize>) -> Self {
+        Self(self.0.with_max_line_width(max_line_width))
+    }
+
+    /// See [`Config::with_highlight_source`].
+    #[inline(always)]
+    pub fn highlight_source(self, highlight_source: bool) -> Self {
+        Self(self.0.with_highlight_source(highlight_source))
+    }
+
+    /// See [`Config::with_show_position`].
+    #[inline(always)]
+    pub fn show_position(self, show_position: bool) -> Self {
+        Self(self.0.with_show_position(show_position))
+    }
+
+    /// See [`Config::with_header_style`].
+    #[inline(always)]
+    pub fn header_style(self, header_style: HeaderStyle) -> Self {
+        Self(self.0.with_header_style(header_style))
+    }
+
+    /// See [`Config::with_unknown_source_name`].
+    #[inline(always)]
+    pub fn unknown_source_name<N>(self, unknown_source_name: N) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        Self(self.0.with_unknown_source_name(unknown_source_name))
+    }
+
+    /// See [`Config::with_hyperlinks`].
+    #[inline(always)]
+    pub fn hyperlinks(self, hyperlinks: HyperlinkChoice) -> Self {
+        Self(self.0.with_hyperlinks(hyperlinks))
+    }
+
+    /// See [`Config::with_localization`].
+    #[inline(always)]
+    pub fn localization(self, localization: Localization) -> Self {
+        Self(self.0.with_localization(localization))
+    }
+
+    /// See [`Config::with_message_transform`].
+    #[inline(always)]
+    pub fn message_transform(self, message_transform: MessageTransform) -> Self {
+        Self(self.0.with_message_transform(message_transform))
+    }
+
+    /// See [`Config::with_header_gutter_row`].
+    #[inline(always)]
+    pub fn header_gutter_row(self, header_gutter_row: bool) -> Self {
+        Self(self.0.with_header_gutter_row(header_gutter_row))
+    }
+
+    /// See [`Config::with_footer_gutter_row`].
+    #[inline(always)]
+    pub fn footer_gutter_row(self, footer_gutter_row: bool) -> Self {
+        Self(self.0.with_footer_gutter_row(footer_gutter_row))
+    }
+
+    /// See [`Config::with_max_labels`].
+    #[inline(always)]
+    pub fn max_labels(self, max_labels: Option<usize>) -> Self {
+        Self(self.0.with_max_labels(max_labels))
+    }
+
+    /// See [`Config::with_block_indent`].
+    #[inline(always)]
+    pub fn block_indent(self, block_indent: usize) -> Self {
+        Self(self.0.with_block_indent(block_indent))
+    }
+
+    /// See [`Config::with_trailing_newline`].
+    #[inline(always)]
+    pub fn trailing_newline(self, trailing_newline: bool) -> Self {
+        Self(self.0.with_trailing_newline(trailing_newline))
+    }
+
+    /// See [`Config::with_clamp_spans`].
+    #[inline(always)]
+    pub fn clamp_spans(self, clamp_spans: bool) -> Self {
+        Self(self.0.with_clamp_spans(clamp_spans))
+    }
+
+    /// See [`Config::with_min_line_number_width`].
+    #[inline(always)]
+    pub fn min_line_number_width(self, width: Option<usize>) -> Self {
+        Self(self.0.with_min_line_number_width(width))
+    }
+
+    /// See [`Config::with_show_ruler`].
+    #[inline(always)]
+    pub fn show_ruler(self, show_ruler: bool) -> Self {
+        Self(self.0.with_show_ruler(show_ruler))
+    }
+
+    /// See [`Config::with_min_underline_width`].
+    #[inline(always)]
+    pub fn min_underline_width(self, width: usize) -> Self {
+        Self(self.0.with_min_underline_width(width))
+    }
+
+    /// See [`Config::with_multiline_connectors`].
+    #[inline(always)]
+    pub fn multiline_connectors(self, multiline_connectors: bool) -> Self {
+        Self(self.0.with_multiline_connectors(multiline_connectors))
+    }
+
+    /// Builds the config.
+    #[inline(always)]
+    pub fn build(self) -> Config {
+        self.0
+    }
 }
@@ -0,0 +1,73 @@
+use super::{config::Config, Diagnostic};
+use crate::source::Source;
+
+/// A [`Diagnostic`] that can be used as an error value.
+///
+/// Since the diagnostic must be `'static`, so must its source. This is
+/// meant to be used by things like parsers, which can return a
+/// `Result<T, DiagnosticError>` and have the error propagated with `?`
+/// (possibly as a `Box<dyn std::error::Error>`).
+///
+/// The [`Display`](std::fmt::Display) implementation renders the diagnostic
+/// without any styling. In order to render it with colors, use the inner
+/// diagnostic directly. Since error reporters expect it to never fail, a
+/// diagnostic that can't be rendered (such as one with a label out of the
+/// bounds of its source) is displayed as just its message:
+/// ```
+/// # use yumy::{Diagnostic, DiagnosticError, Label, Source};
+/// let diagnostic = Diagnostic::new("error: unterminated string")
+///     .with_source(Source::new("let s = \"abc", None))
+///     .with_label(Label::new(8..40, "starts here"));
+///
+/// assert_eq!(DiagnosticError(diagnostic).to_string(), "error: unterminated string");
+/// ```
+///
+/// # Example
+/// ```
+/// use yumy::{Diagnostic, DiagnosticError, Label, Source, SourceSpan};
+///
+/// fn parse_digit(src: &'static str) -> Result<u32, DiagnosticError> {
+///     let digit = src.chars().next().and_then(|c| c.to_digit(10)).ok_or_else(|| {
+///         Diagnostic::new("error: expected a digit")
+///             .with_source(Source::new(src, Some("input")))
+///             .with_label(Label::new(SourceSpan::new(0, 1), "not a digit"))
+///     })?;
+///
+///     Ok(digit)
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     assert_eq!(parse_digit("7")?, 7);
+///     assert!(parse_digit("x").is_err());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiagnosticError(pub Diagnostic<Source<'static>>);
+
+impl DiagnosticError {
+    /// Returns the inner diagnostic.
+    #[inline(always)]
+    pub fn into_inner(self) -> Diagnostic<Source<'static>> {
+        self.0
+    }
+}
+
+impl From<Diagnostic<Source<'static>>> for DiagnosticError {
+    #[inline(always)]
+    fn from(value: Diagnostic<Source<'static>>) -> Self {
+        Self(value)
+    }
+}
+
+impl std::fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rendered = String::new();
+        match self.0.write_to_fmt(&mut rendered, &Config::plain()) {
+            Ok(()) => f.write_str(&rendered),
+            Err(_) => f.write_str(&self.0.message),
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticError {}
@@ -0,0 +1,113 @@
+use super::config::{CarriageReturn, Config};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Returns a visible representation of the given control character, which
+/// is always one column wide for C0 controls and `DEL`.
+fn control_picture(c: char) -> String {
+    match c {
+        // the control pictures block mirrors the C0 controls
+        '\0'..='\x1f' => char::from_u32(0x2400 + c as u32)
+            .expect("control pictures are valid chars")
+            .to_string(),
+        '\x7f' => '\u{2421}'.to_string(),
+        _ => format!("\\x{:02x}", c as u32),
+    }
+}
+
+/// Decides how source text is displayed, escaping the parts of it that
+/// can't be written to a terminal as they are.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Escaper {
+    carriage_return: CarriageReturn,
+    escape_control: bool,
+    strip_ansi: bool,
+}
+
+/// Where in an ANSI escape sequence a grapheme is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SequenceState {
+    /// Not in a sequence.
+    Outside,
+    /// Right after the `ESC` that starts a sequence.
+    Escape,
+    /// In a control sequence (`ESC [`), which ends with a byte in the
+    /// `@..=~` range.
+    Control,
+    /// In an operating system command (`ESC ]`), which ends with `BEL` or
+    /// `ESC \`.
+    Command,
+    /// Right after an `ESC` in an operating system command.
+    CommandEscape,
+}
+
+impl SequenceState {
+    /// Returns the state after the given grapheme, and whether the grapheme
+    /// is part of a sequence.
+    fn next(self, grapheme: &str) -> (Self, bool) {
+        match (self, grapheme) {
+            (Self::Outside, "\x1b") => (Self::Escape, true),
+            (Self::Outside, _) => (Self::Outside, false),
+            (Self::Escape, "[") => (Self::Control, true),
+            (Self::Escape, "]") => (Self::Command, true),
+            (Self::Escape, _) => (Self::Outside, true),
+            (Self::Control, g)
+                if g.len() == 1 && ('@'..='~').contains(&(g.as_bytes()[0] as char)) =>
+            {
+                (Self::Outside, true)
+            }
+            (Self::Control, _) => (Self::Control, true),
+            (Self::Command, "\x07") => (Self::Outside, true),
+            (Self::Command, "\x1b") => (Self::CommandEscape, true),
+            (Self::Command, _) => (Self::Command, true),
+            (Self::CommandEscape, _) => (Self::Outside, true),
+        }
+    }
+}
+
+impl Escaper {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            carriage_return: config.carriage_return,
+            escape_control: config.escape_control,
+            strip_ansi: config.strip_ansi,
+        }
+    }
+
+    /// Returns what should be displayed for the given grapheme.
+    fn escape_grapheme<'t>(&self, grapheme: &'t str) -> Cow<'t, str> {
+        match grapheme {
+            "\r" => match self.carriage_return {
+                CarriageReturn::Strip => Cow::Borrowed(""),
+                CarriageReturn::Escape => Cow::Borrowed("\\r"),
+            },
+            _ if self.escape_control => {
+                let mut chars = grapheme.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c != '\t' && c.is_control() => {
+                        Cow::Owned(control_picture(c))
+                    }
+                    _ => Cow::Borrowed(grapheme),
+                }
+            }
+            _ => Cow::Borrowed(grapheme),
+        }
+    }
+
+    /// Returns the byte index of each grapheme in the given text, together
+    /// with what should be displayed for it.
+    pub fn graphemes<'t>(self, text: &'t str) -> impl Iterator<Item = (usize, Cow<'t, str>)> {
+        let mut state = SequenceState::Outside;
+        text.grapheme_indices(true).map(move |(index, grapheme)| {
+            if self.strip_ansi {
+                let in_sequence;
+                (state, in_sequence) = state.next(grapheme);
+                if in_sequence {
+                    return (index, Cow::Borrowed(""));
+                }
+            }
+
+            (index, self.escape_grapheme(grapheme))
+        })
+    }
+}
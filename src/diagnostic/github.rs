@@ -0,0 +1,103 @@
+use super::{Diagnostic, Severity};
+use crate::source::Source;
+use std::{borrow::Cow, io::Write};
+
+/// Escapes the given text so that it can be used as the message of a GitHub
+/// Actions workflow command. Properties also need `:` and `,` escaped.
+fn escape_command(text: &str, property: bool) -> Cow<'_, str> {
+    let needs_escape =
+        |c: char| matches!(c, '%' | '\r' | '\n') || (property && matches!(c, ':' | ','));
+    if !text.contains(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            '\r' => escaped.push_str("%0D"),
+            '\n' => escaped.push_str("%0A"),
+            ':' if property => escaped.push_str("%3A"),
+            ',' if property => escaped.push_str("%2C"),
+            _ => escaped.push(c),
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Writes this diagnostic as a GitHub Actions workflow command, which
+    /// shows up as an annotation on the file it points to.
+    ///
+    /// The location of the annotation is the span of the primary label. Spans
+    /// covering multiple lines also set the end of the annotation. Diagnostics
+    /// without a [`Severity`] are written as errors, and notes are written as
+    /// notices.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Source, SourceSpan};
+    /// let source = Source::new("fn main() {\n    let x = 5;\n}", Some("src/main.rs"));
+    /// let diagnostic = Diagnostic::warning(source, SourceSpan::new(20, 21), "unused variable: `x`");
+    ///
+    /// let mut output = Vec::new();
+    /// diagnostic.write_github_actions(&mut output).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(output).unwrap(),
+    ///     "::warning file=src/main.rs,line=2,col=9::unused variable: `x`\n",
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, as the
+    /// annotation couldn't be placed.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Source, SourceSpan};
+    /// let source = Source::new("x", Some("f.rs"));
+    /// let diagnostic = Diagnostic::error(source, SourceSpan::new(3, 9), "oob");
+    ///
+    /// let error = diagnostic.write_github_actions(&mut Vec::new()).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn write_github_actions<W>(&self, writer: &mut W) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        self.ensure_in_bounds()?;
+
+        let command = match self.severity {
+            Some(Severity::Error) | None => "error",
+            Some(Severity::Warning) => "warning",
+            Some(Severity::Note) => "notice",
+        };
+
+        let mut properties = Vec::new();
+        if let Some(name) = self.source.name() {
+            properties.push(format!("file={}", escape_command(name, true)));
+        }
+
+        if let Some(label) = self.primary_label() {
+            let start = self.source.position_at(label.span.start());
+            if let Some((line, column)) = start {
+                properties.push(format!("line={line}"));
+                properties.push(format!("col={column}"));
+            }
+
+            let end = self.source.end_position_of(label.span);
+            if let Some((end_line, end_column)) = end.filter(|_| !label.is_singleline(&self.source))
+            {
+                properties.push(format!("endLine={end_line}"));
+                properties.push(format!("endColumn={end_column}"));
+            }
+        }
+
+        write!(writer, "::{command}")?;
+        if !properties.is_empty() {
+            write!(writer, " {}", properties.join(","))?;
+        }
+
+        writeln!(writer, "::{}", escape_command(&self.message, false))
+    }
+}
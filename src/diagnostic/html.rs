@@ -0,0 +1,305 @@
+use super::{config::Config, Diagnostic};
+use crate::source::Source;
+use owo_colors::Style;
+use std::io::Write;
+
+/// A color set by an SGR escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SgrColor {
+    /// One of the 16 ANSI colors, the last 8 being the bright ones.
+    Ansi(u8),
+    /// One of the 256 xterm colors.
+    Xterm(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// The state set by SGR escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct SgrStyle {
+    fg: Option<SgrColor>,
+    bg: Option<SgrColor>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+    blink: bool,
+    blink_fast: bool,
+    reversed: bool,
+    hidden: bool,
+    strikethrough: bool,
+}
+
+/// Formats the SGR escape sequence that enables a [`Style`].
+struct StylePrefix(Style);
+
+impl std::fmt::Display for StylePrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_prefix(f)
+    }
+}
+
+impl SgrStyle {
+    /// Returns the state set by the escape sequence of the given [`Style`].
+    /// Its fields are private, so its sequence is the only way to get to
+    /// them.
+    fn from_style(style: Style) -> Self {
+        let mut sgr = Self::default();
+        let prefix = StylePrefix(style).to_string();
+        if let Some(params) = prefix
+            .strip_prefix("\x1b[")
+            .and_then(|prefix| prefix.strip_suffix('m'))
+        {
+            sgr.apply(params);
+        }
+
+        sgr
+    }
+
+    /// Parses an extended color (`5;n` or `2;r;g;b`) from the given params.
+    fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<SgrColor> {
+        match params.next()? {
+            5 => Some(SgrColor::Xterm(params.next()? as u8)),
+            2 => Some(SgrColor::Rgb(
+                params.next()? as u8,
+                params.next()? as u8,
+                params.next()? as u8,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Applies the given SGR parameters to this style.
+    fn apply(&mut self, params: &str) {
+        let mut params = params.split(';').map(|x| x.parse::<u16>().unwrap_or(0));
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dimmed = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                5 => self.blink = true,
+                6 => self.blink_fast = true,
+                7 => self.reversed = true,
+                8 => self.hidden = true,
+                9 => self.strikethrough = true,
+                22 => (self.bold, self.dimmed) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                25 => (self.blink, self.blink_fast) = (false, false),
+                27 => self.reversed = false,
+                28 => self.hidden = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.fg = Some(SgrColor::Ansi((param - 30) as u8)),
+                38 => self.fg = Self::extended_color(&mut params),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(SgrColor::Ansi((param - 40) as u8)),
+                48 => self.bg = Self::extended_color(&mut params),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(SgrColor::Ansi((param - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(SgrColor::Ansi((param - 100 + 8) as u8)),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// The colors used for the 16 ANSI colors, the last 8 being the bright ones.
+const ANSI_PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+impl SgrColor {
+    /// Returns the RGB components of this color.
+    fn rgb(self) -> (u8, u8, u8) {
+        const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        match self {
+            SgrColor::Ansi(index) => ANSI_PALETTE[index as usize],
+            SgrColor::Xterm(index @ 0..=15) => ANSI_PALETTE[index as usize],
+            SgrColor::Xterm(index @ 16..=231) => {
+                let index = (index - 16) as usize;
+                (
+                    CUBE_STEPS[index / 36],
+                    CUBE_STEPS[(index / 6) % 6],
+                    CUBE_STEPS[index % 6],
+                )
+            }
+            SgrColor::Xterm(index) => {
+                let level = 8 + 10 * (index - 232);
+                (level, level, level)
+            }
+            SgrColor::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// Returns this color as a CSS hex color.
+    fn css(self) -> String {
+        let (r, g, b) = self.rgb();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl SgrStyle {
+    /// Returns the inline CSS equivalent to this style, which is empty if
+    /// this is the default style.
+    fn css(&self) -> String {
+        let (fg, bg) = if self.reversed {
+            (self.bg, self.fg)
+        } else {
+            (self.fg, self.bg)
+        };
+
+        let mut css = Vec::new();
+        if let Some(fg) = fg {
+            css.push(format!("color:{}", fg.css()));
+        }
+
+        if let Some(bg) = bg {
+            css.push(format!("background-color:{}", bg.css()));
+        }
+
+        if self.bold {
+            css.push("font-weight:bold".to_owned());
+        }
+
+        if self.dimmed {
+            css.push("opacity:0.5".to_owned());
+        }
+
+        if self.italic {
+            css.push("font-style:italic".to_owned());
+        }
+
+        let decorations: Vec<_> = [
+            (self.underline, "underline"),
+            (self.strikethrough, "line-through"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, decoration)| enabled.then_some(decoration))
+        .collect();
+
+        if !decorations.is_empty() {
+            css.push(format!("text-decoration:{}", decorations.join(" ")));
+        }
+
+        if self.hidden {
+            css.push("visibility:hidden".to_owned());
+        }
+
+        css.join(";")
+    }
+}
+
+/// Writes the given text with the HTML special characters escaped. Control
+/// characters other than tabs and newlines, such as the escape character, are
+/// written as numeric character references, so that they can't be mistaken
+/// for styling.
+fn write_escaped<W>(writer: &mut W, text: &str) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let mut last = 0;
+    for (index, c) in text.char_indices() {
+        let escaped = match c {
+            '&' => Some("&amp;"),
+            '<' => Some("&lt;"),
+            '>' => Some("&gt;"),
+            '"' => Some("&quot;"),
+            '\'' => Some("&#39;"),
+            '\t' | '\n' => continue,
+            c if c.is_control() => None,
+            _ => continue,
+        };
+
+        write!(writer, "{}", &text[last..index])?;
+        match escaped {
+            Some(escaped) => write!(writer, "{escaped}")?,
+            None => write!(writer, "&#x{:x};", c as u32)?,
+        }
+        last = index + c.len_utf8();
+    }
+
+    write!(writer, "{}", &text[last..])
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Writes this diagnostic to the given [`Write`]r as HTML using the
+    /// specified [`Config`].
+    ///
+    /// The diagnostic is rendered as it would be by [`Diagnostic::write_to`]
+    /// inside of a `<pre>` element, which keeps it aligned. Each styled
+    /// segment becomes a `<span>` with an inline style equivalent to its
+    /// [`Style`], and the text is escaped.
+    /// [`Config::block_indent`] is applied inside of the `<pre>` element, just
+    /// like it would be in the terminal.
+    ///
+    /// ```
+    /// # use yumy::{owo_colors::Style, ColorChoice, Config, Diagnostic, Label, Source, SourceSpan};
+    /// let diagnostic = Diagnostic::new("error: bad comparison")
+    ///     .with_source(Source::new("if a < b {}", Some("main.rs")))
+    ///     .with_label(Label::new(SourceSpan::new(5, 6), "here"));
+    ///
+    /// let mut html = Vec::new();
+    /// diagnostic.write_html(&mut html, &Config::ascii_plain()).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(html).unwrap(),
+    ///     "<pre class=\"yumy\">error: bad comparison\n  \
+    ///      @ [main.rs]\n\
+    ///      1 |  if a &lt; b {}\n  \
+    ///      :       ^ here\n\
+    ///      \n\
+    ///      </pre>\n",
+    /// );
+    ///
+    /// // styles come from the config and labels, never from the source
+    /// let diagnostic = Diagnostic::new("error: bad comparison")
+    ///     .with_source(Source::new("if a < \x1b[1mb {}", Some("main.rs")))
+    ///     .with_label(Label::styled(5..6, "here", Style::new().red().bold()));
+    ///
+    /// let config = Config::ascii_plain().with_color(ColorChoice::Always);
+    /// let mut html = Vec::new();
+    /// diagnostic.write_html(&mut html, &config).unwrap();
+    /// let html = String::from_utf8(html).unwrap();
+    /// assert!(html.contains("if a &lt; &#x1b;[1mb {}"));
+    /// assert!(html.contains("<span style=\"color:#cd0000;font-weight:bold\">^</span> here"));
+    /// ```
+    pub fn write_html<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let lines = self.render_lines(config)?;
+
+        write!(writer, "<pre class=\"yumy\">")?;
+        for line in lines {
+            for (text, style) in line.segments {
+                let css = SgrStyle::from_style(style).css();
+                if css.is_empty() {
+                    write_escaped(writer, &text)?;
+                } else {
+                    write!(writer, "<span style=\"{css}\">")?;
+                    write_escaped(writer, &text)?;
+                    write!(writer, "</span>")?;
+                }
+            }
+
+            writeln!(writer)?;
+        }
+
+        writeln!(writer, "</pre>")
+    }
+}
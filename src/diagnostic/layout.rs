@@ -0,0 +1,256 @@
+use super::{
+    body::{BodyLayout, BodyWriter},
+    config::Config,
+    styled::AnsiWriter,
+    Diagnostic,
+};
+use crate::source::Source;
+use std::ops::Range;
+
+/// The kind of an [`IndicatorLayout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum IndicatorKind {
+    /// The underline of a singleline label.
+    Underline,
+    /// The start of a multiline label.
+    MultilineStart,
+    /// The end of a multiline label.
+    MultilineEnd,
+}
+
+/// Where the indicator of a label is placed on a line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IndicatorLayout {
+    /// The index of the label this indicator belongs to.
+    pub label: usize,
+    /// The kind of this indicator.
+    pub kind: IndicatorKind,
+    /// The 1-based display columns covered by this indicator, end exclusive,
+    /// as described in [`Diagnostic::layout`]. Indicators always cover at
+    /// least one column.
+    pub columns: Range<usize>,
+}
+
+/// A description of how the body of a [`Diagnostic`] is laid out with a
+/// given [`Config`], as returned by [`Diagnostic::describe`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DescriptorView {
+    /// The 1-based line numbers of each run of consecutive lines shown, end
+    /// exclusive, in order.
+    pub chunks: Vec<Range<usize>>,
+    /// The indicators placed on each line, as returned by
+    /// [`Diagnostic::layout`].
+    pub lines: Vec<LineLayout>,
+    /// The number of columns of indentation trimmed from the lines shown.
+    /// See [`Config::dedent`].
+    pub indent_trim: usize,
+    /// The number of columns taken by line numbers.
+    pub line_number_width: usize,
+    /// The maximum number of multiline labels active at the same time, which
+    /// is the number of columns taken by their indicators.
+    pub maximum_parallel_labels: usize,
+}
+
+/// The indicators placed on a line of the source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LineLayout {
+    /// The 1-based line number, including the line offset of the source.
+    pub line: usize,
+    /// The indicators on this line, ordered by their columns.
+    pub indicators: Vec<IndicatorLayout>,
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Returns where the indicators of the labels of this diagnostic are
+    /// placed when written with the given [`Config`], for each line that has
+    /// any, ordered by line number.
+    ///
+    /// This is meant for testing: it allows checking where labels end up
+    /// without matching against rendered output. The placement is the one
+    /// computed when rendering, so it accounts for everything in the config
+    /// that moves indicators, such as [`Config::tab_width`],
+    /// [`Config::max_line_width`] and [`Config::min_underline_width`].
+    /// Columns are 1-based display columns of the line as rendered, starting
+    /// at its text, so they're not affected by the left column or the
+    /// indicators of multiline labels. Each span of a label gets an indicator
+    /// of its own, and labels hidden by [`Config::max_labels`] are left out.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, unless
+    /// [`Config::clamp_spans`] is enabled.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, IndicatorKind, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: mismatched types")
+    ///     .with_source(Source::new("let x: u32 = \"five\";", None))
+    ///     .with_label(Label::new(7..10, "expected because of this"))
+    ///     .with_label(Label::new(13..19, "found `&str`"));
+    ///
+    /// let layout = diagnostic.layout(&Config::default()).unwrap();
+    /// assert_eq!(layout.len(), 1);
+    /// assert_eq!(layout[0].line, 1);
+    ///
+    /// let indicators = &layout[0].indicators;
+    /// assert_eq!(indicators[0].label, 0);
+    /// assert_eq!(indicators[0].kind, IndicatorKind::Underline);
+    /// assert_eq!(indicators[0].columns, 8..11);
+    /// assert_eq!(indicators[1].label, 1);
+    /// assert_eq!(indicators[1].columns, 14..20);
+    /// ```
+    ///
+    /// Columns match the rendered indicators, whatever the config:
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let\tx = y;", None))
+    ///     .with_label(Label::new(8..9, "not found"));
+    ///
+    /// let config = Config::plain().with_tab_width(2).with_min_underline_width(3);
+    /// let layout = diagnostic.layout(&config).unwrap();
+    /// assert_eq!(layout[0].indicators[0].columns, 9..12);
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains("\n  :          ^^^ not found\n"));
+    /// ```
+    pub fn layout(&self, config: &Config) -> std::io::Result<Vec<LineLayout>> {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.layout(config);
+        }
+
+        let layout = BodyLayout::new(&self.source, &self.labels);
+        let mut body = BodyWriter::new(
+            AnsiWriter(std::io::sink()),
+            &self.source,
+            config,
+            self.left_padding(),
+            &layout,
+        );
+
+        let mut indicators = body.indicators();
+        indicators
+            .sort_by_key(|(line, indicator)| (*line, indicator.columns.start, indicator.label));
+
+        let mut lines: Vec<LineLayout> = Vec::new();
+        for (index, indicator) in indicators {
+            let line = self.source.line_number(index);
+            match lines.last_mut() {
+                Some(last) if last.line == line => last.indicators.push(indicator),
+                _ => lines.push(LineLayout {
+                    line,
+                    indicators: vec![indicator],
+                }),
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Returns a description of how the body of this diagnostic is laid out
+    /// when written with the given [`Config`]. Like [`Diagnostic::layout`],
+    /// this is meant for inspecting layout decisions without going through
+    /// rendered output. With the `serde` feature enabled, the description
+    /// can be serialized, such as to dump it as JSON.
+    ///
+    /// The description is specific to the config: the columns, the lines
+    /// shown and the indentation trimmed all depend on settings such as
+    /// [`Config::tab_width`], [`Config::dedent`] and [`Config::max_labels`],
+    /// so descriptions made with different configs shouldn't be compared.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, unless
+    /// [`Config::clamp_spans`] is enabled.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, DescriptorView, IndicatorKind, IndicatorLayout, Label, LineLayout, Source};
+    /// let src = "fn main() {\n    let x = 5;\n}\n\nfn unused() {}";
+    /// let diagnostic = Diagnostic::new("error: unclosed block")
+    ///     .with_source(Source::new(src, None))
+    ///     .with_label(Label::new(10..29, "this block"))
+    ///     .with_label(Label::new(33..39, "unused"));
+    ///
+    /// let view = diagnostic.describe(&Config::default()).unwrap();
+    /// assert_eq!(
+    ///     view,
+    ///     DescriptorView {
+    ///         chunks: vec![1..4, 5..6],
+    ///         lines: vec![
+    ///             LineLayout {
+    ///                 line: 1,
+    ///                 indicators: vec![IndicatorLayout {
+    ///                     label: 0,
+    ///                     kind: IndicatorKind::MultilineStart,
+    ///                     columns: 11..12,
+    ///                 }],
+    ///             },
+    ///             LineLayout {
+    ///                 line: 3,
+    ///                 indicators: vec![IndicatorLayout {
+    ///                     label: 0,
+    ///                     kind: IndicatorKind::MultilineEnd,
+    ///                     columns: 1..2,
+    ///                 }],
+    ///             },
+    ///             LineLayout {
+    ///                 line: 5,
+    ///                 indicators: vec![IndicatorLayout {
+    ///                     label: 1,
+    ///                     kind: IndicatorKind::Underline,
+    ///                     columns: 4..10,
+    ///                 }],
+    ///             },
+    ///         ],
+    ///         indent_trim: 0,
+    ///         line_number_width: 1,
+    ///         maximum_parallel_labels: 1,
+    ///     }
+    /// );
+    ///
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// let json = serde_json::to_string(&view).unwrap();
+    /// assert!(json.contains(r#""chunks":[{"start":1,"end":4},{"start":5,"end":6}]"#));
+    /// # }
+    /// ```
+    pub fn describe(&self, config: &Config) -> std::io::Result<DescriptorView> {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.describe(config);
+        }
+
+        let line_number_width = self
+            .left_padding()
+            .max(config.min_line_number_width.unwrap_or_default());
+
+        let layout = BodyLayout::new(&self.source, &self.labels);
+        let body = BodyWriter::new(
+            AnsiWriter(std::io::sink()),
+            &self.source,
+            config,
+            line_number_width,
+            &layout,
+        );
+
+        let mut chunks: Vec<Range<usize>> = Vec::new();
+        for line in body.shown_lines() {
+            let line = self.source.line_number(line);
+            match chunks.last_mut() {
+                Some(last) if last.end == line => last.end += 1,
+                _ => chunks.push(line..line + 1),
+            }
+        }
+
+        Ok(DescriptorView {
+            chunks,
+            lines: self.layout(config)?,
+            indent_trim: body.ident_len(),
+            line_number_width,
+            maximum_parallel_labels: body.slots_needed(),
+        })
+    }
+}
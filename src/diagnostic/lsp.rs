@@ -0,0 +1,81 @@
+use super::{Diagnostic, Label, Severity};
+use crate::source::Source;
+use lsp_types::{
+    CodeDescription, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Uri,
+};
+
+impl Diagnostic<Source<'_>> {
+    /// Returns the LSP range of the given label, or `None` if its span is out
+    /// of bounds.
+    fn lsp_range(&self, label: &Label) -> Option<Range> {
+        let position = |byte| {
+            let (line, character) = self.source.utf16_position_at(byte)?;
+            Some(Position::new(line as u32, character as u32))
+        };
+
+        Some(Range::new(
+            position(label.span.start())?,
+            position(label.span.end())?,
+        ))
+    }
+
+    /// Converts this diagnostic into an LSP diagnostic for the document with
+    /// the given URI.
+    ///
+    /// The range of the LSP diagnostic is the span of the primary label, with
+    /// columns counted in UTF-16 code units as required by the protocol, and
+    /// the other labels become related information. The
+    /// [code](Diagnostic::with_code) and its URL are kept, if any.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Source, SourceSpan};
+    /// # use lsp_types::{DiagnosticSeverity, Position, Range, Uri};
+    /// // the emoji takes 4 bytes, 2 UTF-16 code units and 2 columns
+    /// let source = Source::new("let 🦀 = crab;", None);
+    /// let diagnostic = Diagnostic::error(source, SourceSpan::new(11, 15), "unknown variable");
+    ///
+    /// let uri: Uri = "file:///src/main.rs".parse().unwrap();
+    /// let lsp = diagnostic.to_lsp(&uri);
+    /// assert_eq!(lsp.range, Range::new(Position::new(0, 9), Position::new(0, 13)));
+    /// assert_eq!(lsp.severity, Some(DiagnosticSeverity::ERROR));
+    /// ```
+    pub fn to_lsp(&self, uri: &Uri) -> lsp_types::Diagnostic {
+        let primary = self.primary_label();
+        let range = primary
+            .and_then(|label| self.lsp_range(label))
+            .unwrap_or_default();
+
+        let severity = self.severity.map(|severity| match severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Note => DiagnosticSeverity::INFORMATION,
+        });
+
+        let related_information: Vec<_> = self
+            .labels
+            .iter()
+            .filter(|label| !primary.is_some_and(|primary| std::ptr::eq(*label, primary)))
+            .filter_map(|label| {
+                Some(DiagnosticRelatedInformation {
+                    location: Location::new(uri.clone(), self.lsp_range(label)?),
+                    message: label.message.clone(),
+                })
+            })
+            .collect();
+
+        lsp_types::Diagnostic {
+            range,
+            severity,
+            code: self.code.clone().map(NumberOrString::String),
+            code_description: self
+                .code_url
+                .as_deref()
+                .and_then(|url| url.parse().ok())
+                .map(|href| CodeDescription { href }),
+            message: self.message.clone(),
+            related_information: (!related_information.is_empty()).then_some(related_information),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::{
+    config::{ColorChoice, Config},
+    Diagnostic,
+};
+use crate::source::Source;
+use std::io::Write;
+
+/// Escapes the characters of the given text that have a meaning in Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '~' | '|'
+        ) {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Returns the length of the longest run of backticks in the given text.
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Writes this diagnostic to the given [`Write`]r as a Markdown code
+    /// block using the specified [`Config`], which is useful for pasting
+    /// diagnostics into issues. Colors are never used.
+    ///
+    /// If `bold_message` is set, the first line of the message of the
+    /// diagnostic (preceded by its severity, if any) is written as a bold
+    /// line before the code block. The fence
+    /// of the code block is made longer than any run of backticks inside of
+    /// it, so sources with backticks don't break it.
+    /// [`Config::block_indent`] only applies to the lines inside of the code
+    /// block, as indenting the fences or the bold line would change their
+    /// meaning in Markdown.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source, SourceSpan};
+    /// let diagnostic = Diagnostic::new("error: unclosed code span")
+    ///     .with_source(Source::new("docs: ```rust", Some("README.md")))
+    ///     .with_label(Label::new(SourceSpan::new(6, 9), "opened here"));
+    ///
+    /// let mut markdown = Vec::new();
+    /// diagnostic.write_markdown(&mut markdown, &Config::ascii_plain(), true).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(markdown).unwrap(),
+    ///     "**error: unclosed code span**\n\
+    ///      \n\
+    ///      ````\n\
+    ///      error: unclosed code span\n  \
+    ///      @ [README.md]\n\
+    ///      1 |  docs: ```rust\n  \
+    ///      :        ^^^ opened here\n\
+    ///      ````\n",
+    /// );
+    /// ```
+    pub fn write_markdown<W>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        bold_message: bool,
+    ) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let config = Config {
+            color: ColorChoice::Never,
+            ..config.clone()
+        };
+
+        let mut rendered = Vec::new();
+        self.write_to(&mut rendered, &config)?;
+        let rendered = String::from_utf8_lossy(&rendered);
+        let rendered = rendered.trim_end_matches('\n');
+
+        if bold_message {
+            let mut message = self.message.lines().next().unwrap_or_default().to_owned();
+            if let Some(severity) = self.severity {
+                message = format!("{}: {message}", config.localization.severity(severity));
+            }
+
+            writeln!(writer, "**{}**", escape_markdown(&message))?;
+            writeln!(writer)?;
+        }
+
+        let fence = "`".repeat(longest_backtick_run(rendered).max(2) + 1);
+        writeln!(writer, "{fence}")?;
+        writeln!(writer, "{rendered}")?;
+        writeln!(writer, "{fence}")
+    }
+}
@@ -0,0 +1,136 @@
+use super::{config::Config, styled::StyledWrite, Diagnostic};
+use crate::{source::Source, text::WidthOptions};
+use owo_colors::Style;
+use std::fmt::Display;
+
+/// A writer that measures the lines written to it instead of storing them.
+/// Styles and hyperlinks take no columns, so they're left out.
+struct LineMeasurer {
+    width_options: WidthOptions,
+    /// The text of the line being written.
+    current: String,
+    max_width: usize,
+    /// The number of lines finished so far.
+    lines: usize,
+}
+
+impl LineMeasurer {
+    fn new(width_options: WidthOptions) -> Self {
+        Self {
+            width_options,
+            current: String::new(),
+            max_width: 0,
+            lines: 0,
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let width = self.width_options.display_width(&self.current);
+        self.max_width = self.max_width.max(width);
+        self.current.clear();
+        self.lines += 1;
+    }
+
+    /// Measures the lines of the given diagnostic as written with
+    /// [`Diagnostic::write_to`].
+    fn measure(diagnostic: &Diagnostic<Source<'_>>, config: &Config) -> std::io::Result<Self> {
+        if let Some(clamped) = diagnostic.check_bounds(config)? {
+            return Self::measure(&clamped, config);
+        }
+
+        let mut measurer = Self::new(config.width_options());
+        diagnostic.prepare()?.write_styled(&mut measurer, config)?;
+
+        if !measurer.current.is_empty() {
+            measurer.finish_line();
+        }
+
+        Ok(measurer)
+    }
+}
+
+impl StyledWrite for LineMeasurer {
+    fn write_styled<T>(&mut self, text: T, _: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        let text = text.to_string();
+        let mut lines = text.split('\n');
+        if let Some(first) = lines.next() {
+            self.current.push_str(first);
+        }
+
+        for line in lines {
+            self.finish_line();
+            self.current.push_str(line);
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Returns the width, in columns, of the widest line this diagnostic
+    /// occupies when written with [`Diagnostic::write_to`] using the given
+    /// [`Config`]. The output is measured as it's produced, so nothing is
+    /// actually written. Useful for sizing viewports in TUIs.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, unless
+    /// [`Config::clamp_spans`] is enabled.
+    ///
+    /// ```
+    /// # use yumy::{text::display_width, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let 名前 = 値;", Some("main.rs")))
+    ///     .with_label(Label::new(13..16, "not found"))
+    ///     .with_note("variables must be declared before use");
+    ///
+    /// let config = Config::plain();
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    ///
+    /// let widest = rendered.lines().map(display_width).max().unwrap();
+    /// assert_eq!(diagnostic.render_width(&config).unwrap(), widest);
+    ///
+    /// let out_of_bounds = diagnostic.with_label(Label::new(20..30, "past the end"));
+    /// assert!(out_of_bounds.render_width(&config).is_err());
+    /// ```
+    pub fn render_width(&self, config: &Config) -> std::io::Result<usize> {
+        Ok(LineMeasurer::measure(self, config)?.max_width)
+    }
+
+    /// Returns the number of lines written by [`Diagnostic::write_to`] with
+    /// the given [`Config`], including the empty one at the end if
+    /// [`Config::trailing_newline`] is enabled. Like
+    /// [`Diagnostic::render_width`], nothing is actually written.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, unless
+    /// [`Config::clamp_spans`] is enabled.
+    ///
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unterminated block")
+    ///     .with_source(Source::new("fn main() {\n    let x = 5;\n", Some("main.rs")))
+    ///     .with_label(Label::new(10..26, "this block"))
+    ///     .with_label(Label::new(20..21, "unused").with_note("prefix it with `_`"))
+    ///     .with_note("blocks must be closed");
+    ///
+    /// for config in [Config::plain(), Config::plain().with_trailing_newline(false)] {
+    ///     let mut rendered = String::new();
+    ///     diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    ///
+    ///     let lines = rendered.matches('\n').count();
+    ///     assert_eq!(diagnostic.rendered_line_count(&config).unwrap(), lines);
+    /// }
+    ///
+    /// let out_of_bounds = diagnostic.with_label(Label::new(40..50, "past the end"));
+    /// assert!(out_of_bounds.rendered_line_count(&Config::plain()).is_err());
+    /// ```
+    pub fn rendered_line_count(&self, config: &Config) -> std::io::Result<usize> {
+        Ok(LineMeasurer::measure(self, config)?.lines)
+    }
+}
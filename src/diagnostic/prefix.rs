@@ -0,0 +1,148 @@
+use super::{config::Config, styled::StyledWrite};
+use owo_colors::Style;
+use std::{fmt::Display, io::Write};
+
+/// Adapter that writes a prefix at the start of every line written through
+/// it, useful for nesting diagnostics inside other output. The prefix is only
+/// written once a line has some content, so empty lines are left empty.
+///
+/// Writes may start and end anywhere, including in the middle of a line.
+///
+/// ```
+/// # use std::io::Write;
+/// # use yumy::LinePrefixWriter;
+/// let mut writer = LinePrefixWriter::new(Vec::new(), "> ");
+/// write!(writer, "first line\nsec").unwrap();
+/// write!(writer, "ond line\n\nthird").unwrap();
+/// write!(writer, " line\n").unwrap();
+///
+/// let output = String::from_utf8(writer.into_inner()).unwrap();
+/// assert_eq!(output, "> first line\n> second line\n\n> third line\n");
+/// ```
+#[derive(Debug)]
+pub struct LinePrefixWriter<W> {
+    inner: W,
+    prefix: String,
+    at_line_start: bool,
+}
+
+impl<W> LinePrefixWriter<W> {
+    /// Creates a new adapter that writes `prefix` at the start of every line
+    /// written to `inner`.
+    pub fn new<P>(inner: W, prefix: P) -> Self
+    where
+        P: Into<String>,
+    {
+        Self {
+            inner,
+            prefix: prefix.into(),
+            at_line_start: true,
+        }
+    }
+
+    /// Returns a reference to the underlying writer.
+    #[inline(always)]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer. Writing to it
+    /// directly bypasses the prefix.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the underlying writer.
+    #[inline(always)]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W> Write for LinePrefixWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let Some(&first) = buf.first() else {
+            return Ok(0);
+        };
+
+        if self.at_line_start && first != b'\n' {
+            self.inner.write_all(self.prefix.as_bytes())?;
+            self.at_line_start = false;
+        }
+
+        // only write up to the end of the current line, so that the prefix
+        // of the next one is written before it
+        let line_end = buf
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(buf.len(), |i| i + 1);
+
+        let written = self.inner.write(&buf[..line_end])?;
+        if written > 0 && buf[written - 1] == b'\n' {
+            self.at_line_start = true;
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Wraps the given writer so that every line written through it is indented
+/// by [`Config::block_indent`].
+#[inline]
+pub(crate) fn block_indented<W>(writer: W, config: &Config) -> LinePrefixWriter<W> {
+    LinePrefixWriter::new(writer, " ".repeat(config.block_indent))
+}
+
+impl<W> StyledWrite for LinePrefixWriter<W>
+where
+    W: StyledWrite,
+{
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        if self.prefix.is_empty() {
+            return self.inner.write_styled(text, style);
+        }
+
+        let text = text.to_string();
+        for line in text.split_inclusive('\n') {
+            if self.at_line_start && line != "\n" {
+                self.inner.write_styled(&self.prefix, Style::new())?;
+                self.at_line_start = false;
+            }
+
+            match line.strip_suffix('\n') {
+                Some(line) => {
+                    self.inner.write_styled(line, style)?;
+                    self.inner.write_styled('\n', Style::new())?;
+                    self.at_line_start = true;
+                }
+                None => self.inner.write_styled(line, style)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_link<T>(&mut self, url: &str, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        // links never span lines
+        if self.at_line_start && !self.prefix.is_empty() {
+            self.inner.write_styled(&self.prefix, Style::new())?;
+            self.at_line_start = false;
+        }
+
+        self.inner.write_link(url, text, style)
+    }
+}
@@ -0,0 +1,146 @@
+use super::{
+    body::BodyLayout,
+    config::Config,
+    prefix::block_indented,
+    styled::{AnsiWriter, StyledWrite},
+    Diagnostic, Label,
+};
+use crate::source::Source;
+use std::io::Write;
+
+/// A [`Diagnostic`] with the parts of its rendering that don't depend on a
+/// [`Config`] already computed, such as the layout of its labels. Useful
+/// when rendering the same diagnostic many times with different configs.
+#[derive(Debug, Clone)]
+pub struct PreparedDiagnostic<'a, 'src> {
+    diagnostic: &'a Diagnostic<Source<'src>>,
+    left_padding: usize,
+    layout: BodyLayout<'a>,
+}
+
+impl<'src> Diagnostic<Source<'src>> {
+    /// Prepares this diagnostic to be rendered, computing the parts of its
+    /// rendering that don't depend on a [`Config`].
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source. Since the
+    /// config isn't known yet, [`Config::clamp_spans`] doesn't apply here:
+    /// use [`Diagnostic::write_to`] to render such diagnostics clamped.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unexpected end of input")
+    ///     .with_source(Source::new("", None))
+    ///     .with_label(Label::new(0..1, "here"));
+    ///
+    /// let error = diagnostic.prepare().unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn prepare(&self) -> std::io::Result<PreparedDiagnostic<'_, 'src>> {
+        self.ensure_in_bounds()?;
+
+        Ok(PreparedDiagnostic {
+            diagnostic: self,
+            left_padding: self.left_padding(),
+            layout: BodyLayout::new(&self.source, &self.labels),
+        })
+    }
+
+    /// Writes this diagnostic to the given [`Write`]r using the specified
+    /// [`Config`], showing only the labels that match the given filter. The
+    /// footnotes are still shown, and the numbers of the labels shown are the
+    /// same as if all of them were.
+    ///
+    /// ```
+    /// # use yumy::{owo_colors::Style, ColorChoice, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: mismatched types")
+    ///     .with_source(Source::new("let x: u32 = \"five\";", None))
+    ///     .with_label(Label::new(7..10, "expected because of this"))
+    ///     .with_label(Label::styled(13..19, "found `&str`", Style::new().red()));
+    ///
+    /// let config = Config::default().with_color(ColorChoice::Never);
+    /// let mut rendered = Vec::new();
+    /// diagnostic
+    ///     .write_filtered(&mut rendered, &config, |label| label.indicator_style.is_some())
+    ///     .unwrap();
+    ///
+    /// let rendered = String::from_utf8(rendered).unwrap();
+    /// assert!(rendered.contains("found `&str`"));
+    /// assert!(!rendered.contains("expected because of this"));
+    /// ```
+    pub fn write_filtered<W, F>(
+        &self,
+        writer: &mut W,
+        config: &Config,
+        filter: F,
+    ) -> std::io::Result<()>
+    where
+        W: Write,
+        F: Fn(&Label) -> bool,
+    {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.write_filtered(writer, config, filter);
+        }
+
+        let prepared = PreparedDiagnostic {
+            diagnostic: self,
+            left_padding: self.left_padding_of(self.labels.iter().filter(|label| filter(label))),
+            layout: BodyLayout::filtered(&self.source, &self.labels, filter),
+        };
+
+        prepared.write_to(writer, config)
+    }
+}
+
+impl PreparedDiagnostic<'_, '_> {
+    /// Writes this diagnostic to the given [`Write`]r using the specified
+    /// [`Config`]. See [`Diagnostic::write_to`].
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source, SourceSpan};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let x = y;", Some("main.rs")))
+    ///     .with_label(Label::new(SourceSpan::new(8, 9), "not found"));
+    ///
+    /// let config = Config::default()
+    ///     .with_color(ColorChoice::Never)
+    ///     .with_block_indent(4);
+    ///
+    /// let mut rendered = Vec::new();
+    /// diagnostic.prepare().unwrap().write_to(&mut rendered, &config).unwrap();
+    /// let rendered = String::from_utf8(rendered).unwrap();
+    /// assert!(rendered.starts_with("    error: unknown variable\n"));
+    /// assert!(rendered.lines().all(|line| line.is_empty() || line.starts_with("    ")));
+    /// ```
+    pub fn write_to<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        self.write_styled(&mut AnsiWriter(writer), config)
+    }
+
+    /// Writes this diagnostic to the given [`StyledWrite`]r using the
+    /// specified [`Config`].
+    pub(crate) fn write_styled<W>(&self, writer: &mut W, config: &Config) -> std::io::Result<()>
+    where
+        W: StyledWrite,
+    {
+        let writer = &mut block_indented(writer, config);
+        let diagnostic = self.diagnostic;
+        let config = &*diagnostic.resolve_config(config, true);
+        let left_padding = self
+            .left_padding
+            .max(config.min_line_number_width.unwrap_or_default());
+
+        diagnostic.write_header(writer, config, left_padding)?;
+        diagnostic.write_body(writer, config, left_padding, &self.layout)?;
+        diagnostic.write_footnotes(writer, config, left_padding)?;
+
+        if config.trailing_newline {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
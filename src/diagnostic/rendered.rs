@@ -0,0 +1,115 @@
+use super::{config::Config, styled::StyledWrite, Diagnostic};
+use crate::source::Source;
+use owo_colors::Style;
+use std::fmt::Display;
+
+/// A line of a rendered diagnostic, made of styled segments of text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderedLine {
+    /// The segments of this line, in order.
+    pub segments: Vec<(String, Style)>,
+}
+
+impl RenderedLine {
+    /// Pushes a segment, merging it with the last one if they have the same
+    /// style.
+    fn push(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+
+        match self.segments.last_mut() {
+            Some((last, last_style)) if *last_style == style => last.push_str(text),
+            _ => self.segments.push((text.to_owned(), style)),
+        }
+    }
+
+    /// Returns the text of this line, without any styling.
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+}
+
+/// Collects styled text into [`RenderedLine`]s.
+#[derive(Debug, Default)]
+pub(crate) struct SegmentWriter {
+    lines: Vec<RenderedLine>,
+    current: RenderedLine,
+}
+
+impl SegmentWriter {
+    /// Returns the lines written, including the last one if it isn't empty.
+    pub fn finish(mut self) -> Vec<RenderedLine> {
+        if !self.current.segments.is_empty() {
+            self.lines.push(self.current);
+        }
+
+        self.lines
+    }
+}
+
+impl StyledWrite for SegmentWriter {
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        let text = text.to_string();
+        let mut lines = text.split('\n');
+        if let Some(first) = lines.next() {
+            self.current.push(first, style);
+        }
+
+        for line in lines {
+            self.lines.push(std::mem::take(&mut self.current));
+            self.current.push(line, style);
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Renders this diagnostic into lines of styled segments, in the same way
+    /// as [`Diagnostic::write_to`] would. This is meant for embedding
+    /// diagnostics in TUIs and other contexts where ANSI escape sequences
+    /// can't be used: the segments don't contain any, and their styles can
+    /// be translated to whatever the target uses.
+    ///
+    /// The segments are produced directly by the renderer, so text in the
+    /// source that looks like an escape sequence stays part of the text.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Label, Source};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let a = \x1b[31mred;", None))
+    ///     .with_label(Label::new(13..16, "not found"));
+    ///
+    /// let config = Config::default().with_color(ColorChoice::Always);
+    /// let lines = diagnostic.render_lines(&config).unwrap();
+    /// assert_eq!(lines[2].text(), "1 │  let a = \x1b[31mred;");
+    /// assert!(lines.iter().all(|line| line.text() != "1 │  let a = red;"));
+    ///
+    /// // out of bounds labels are an error, like with `write_to`
+    /// let out_of_bounds = diagnostic.with_label(Label::new(20..30, "past the end"));
+    /// let error = out_of_bounds.render_lines(&config).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidInput`](std::io::ErrorKind::InvalidInput)
+    /// if the span of any label goes past the end of the source, unless
+    /// [`Config::clamp_spans`] is enabled.
+    pub fn render_lines(&self, config: &Config) -> std::io::Result<Vec<RenderedLine>> {
+        if let Some(clamped) = self.check_bounds(config)? {
+            return clamped.render_lines(config);
+        }
+
+        let mut writer = SegmentWriter::default();
+        self.prepare()?.write_styled(&mut writer, config)?;
+
+        Ok(writer.finish())
+    }
+}
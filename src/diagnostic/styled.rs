@@ -0,0 +1,111 @@
+use owo_colors::{OwoColorize, Style};
+use std::{borrow::Cow, fmt::Display, io::Write};
+
+/// Destination of rendered diagnostics, which receives text together with
+/// its style instead of ANSI escape sequences. [`AnsiWriter`] turns it into
+/// escape sequences for terminals, while other writers (such as the one
+/// behind [`Diagnostic::render_lines`](super::Diagnostic::render_lines))
+/// keep them apart.
+pub(crate) trait StyledWrite {
+    /// Writes the given text with the given style.
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display;
+
+    /// Writes the given text with the given style as a hyperlink to the
+    /// given URL. By default, the URL is dropped.
+    fn write_link<T>(&mut self, url: &str, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        let _ = url;
+        self.write_styled(text, style)
+    }
+
+    /// Writes the given unstyled text. This allows using [`write!`] and
+    /// [`writeln!`] for text that isn't styled.
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::io::Result<()> {
+        self.write_styled(args, Style::new())
+    }
+}
+
+impl<S> StyledWrite for &mut S
+where
+    S: StyledWrite + ?Sized,
+{
+    #[inline(always)]
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        (**self).write_styled(text, style)
+    }
+
+    #[inline(always)]
+    fn write_link<T>(&mut self, url: &str, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        (**self).write_link(url, text, style)
+    }
+
+    #[inline(always)]
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::io::Result<()> {
+        (**self).write_fmt(args)
+    }
+}
+
+/// Percent-encodes the control characters of the given URL, which could
+/// otherwise end the hyperlink sequence it's written in early.
+fn escape_url(url: &str) -> Cow<'_, str> {
+    if !url.chars().any(char::is_control) {
+        return Cow::Borrowed(url);
+    }
+
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_control() {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Writes styled text to a [`Write`]r using ANSI escape sequences, and
+/// hyperlinks as OSC 8 sequences.
+pub(crate) struct AnsiWriter<W>(pub W);
+
+impl<W> StyledWrite for AnsiWriter<W>
+where
+    W: Write,
+{
+    #[inline]
+    fn write_styled<T>(&mut self, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        write!(self.0, "{}", text.style(style))
+    }
+
+    fn write_link<T>(&mut self, url: &str, text: T, style: Style) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        write!(
+            self.0,
+            "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+            escape_url(url),
+            text.style(style)
+        )
+    }
+
+    #[inline]
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> std::io::Result<()> {
+        self.0.write_fmt(args)
+    }
+}
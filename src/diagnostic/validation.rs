@@ -0,0 +1,86 @@
+use super::Diagnostic;
+use crate::source::Source;
+
+/// An issue found when validating a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The message of the diagnostic is empty.
+    EmptyMessage,
+    /// A span of the label with the given index goes past the end of the
+    /// source.
+    SpanOutOfBounds { label: usize },
+    /// A span of the label with the given index doesn't start or end at a
+    /// char boundary.
+    SpanNotOnCharBoundary { label: usize },
+    /// The labels with the given indices have the same span, so their
+    /// indicators can't be told apart.
+    IndistinguishableLabels { first: usize, second: usize },
+    /// The footnote with the given index references a label that doesn't
+    /// exist, so the reference isn't shown.
+    FootnoteReferenceOutOfRange { footnote: usize, label: usize },
+}
+
+impl Diagnostic<Source<'_>> {
+    /// Checks this diagnostic for issues that would make it render poorly (or
+    /// panic) and returns all of them, if any.
+    ///
+    /// ```
+    /// # use yumy::{Diagnostic, Footnote, Label, Source, ValidationIssue};
+    /// let diagnostic = Diagnostic::new("error: unknown variable")
+    ///     .with_source(Source::new("let x = y;", None))
+    ///     .with_label(Label::new(8..9, "not found"))
+    ///     .with_footnote(Footnote::new("declared here").referencing(1));
+    ///
+    /// assert_eq!(
+    ///     diagnostic.validate(),
+    ///     Err(vec![ValidationIssue::FootnoteReferenceOutOfRange { footnote: 0, label: 1 }]),
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+        if self.message.trim().is_empty() {
+            issues.push(ValidationIssue::EmptyMessage);
+        }
+
+        let src = self.source.src();
+        for (index, label) in self.labels.iter().enumerate() {
+            let out_of_bounds = label.spans().any(|span| span.end() as usize > src.len());
+            let off_boundary = label.spans().any(|span| {
+                !src.is_char_boundary(span.start() as usize)
+                    || !src.is_char_boundary(span.end() as usize)
+            });
+
+            if out_of_bounds {
+                issues.push(ValidationIssue::SpanOutOfBounds { label: index });
+            } else if off_boundary {
+                issues.push(ValidationIssue::SpanNotOnCharBoundary { label: index });
+            }
+
+            let duplicate = self.labels[..index]
+                .iter()
+                .position(|other| other.span == label.span);
+
+            if let Some(first) = duplicate {
+                issues.push(ValidationIssue::IndistinguishableLabels {
+                    first,
+                    second: index,
+                });
+            }
+        }
+
+        for (index, footnote) in self.footnotes.iter().enumerate() {
+            if let Some(label) = footnote.label.filter(|&label| label >= self.labels.len()) {
+                issues.push(ValidationIssue::FootnoteReferenceOutOfRange {
+                    footnote: index,
+                    label,
+                });
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
@@ -2,17 +2,46 @@
 
 /// Module for diagnostic related items.
 pub mod diagnostic;
+/// Module for SARIF output.
+#[cfg(feature = "sarif")]
+pub mod sarif;
 /// Module for source related items.
 pub mod source;
+/// Module for text related items.
+pub mod text;
 
 pub use owo_colors;
 
+pub use diagnostic::eprint_all;
+pub use diagnostic::write_all;
+pub use diagnostic::DescriptorView;
 pub use diagnostic::Diagnostic;
+pub use diagnostic::DiagnosticError;
+pub use diagnostic::Footnote;
+pub use diagnostic::FootnoteKind;
+pub use diagnostic::IndicatorKind;
+pub use diagnostic::IndicatorLayout;
 pub use diagnostic::Label;
+pub use diagnostic::LineLayout;
+pub use diagnostic::LinePrefixWriter;
+pub use diagnostic::PreparedDiagnostic;
+pub use diagnostic::RenderedLine;
+pub use diagnostic::Severity;
+pub use diagnostic::ValidationIssue;
 
+pub use diagnostic::config::CarriageReturn;
 pub use diagnostic::config::Charset;
+pub use diagnostic::config::ColorChoice;
 pub use diagnostic::config::Config;
+pub use diagnostic::config::ConfigBuilder;
+pub use diagnostic::config::DedentMode;
 pub use diagnostic::config::DefaultStyles;
+pub use diagnostic::config::HeaderStyle;
+pub use diagnostic::config::HyperlinkChoice;
+pub use diagnostic::config::LineNumberAlign;
+pub use diagnostic::config::Localization;
+pub use diagnostic::config::MessageTransform;
 
 pub use source::Source;
+pub use source::SourceLine;
 pub use source::SourceSpan;
@@ -0,0 +1,142 @@
+//! Conversion of diagnostics into [SARIF](https://sarifweb.azurewebsites.net/),
+//! the Static Analysis Results Interchange Format.
+
+use crate::{Diagnostic, Label, Severity, Source};
+use serde_json::{json, Map, Value};
+
+/// The version of SARIF produced by [`to_sarif`].
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// The schema of the version of SARIF produced by [`to_sarif`].
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Returns the SARIF level of the given severity.
+fn level(severity: Option<Severity>) -> &'static str {
+    match severity {
+        Some(Severity::Error) | None => "error",
+        Some(Severity::Warning) => "warning",
+        Some(Severity::Note) => "note",
+    }
+}
+
+/// Converts a label into a SARIF location, or `None` if its span is out of
+/// bounds.
+fn location(source: &Source, label: &Label) -> Option<Value> {
+    // SARIF positions are 1-based, with columns in UTF-16 code units
+    let position = |byte| {
+        let (line, column) = source.utf16_position_at(byte)?;
+        Some((line + 1, column + 1))
+    };
+
+    let (start_line, start_column) = position(label.span.start())?;
+    let (end_line, end_column) = position(label.span.end())?;
+
+    let mut physical = Map::new();
+    if let Some(name) = source.name() {
+        physical.insert("artifactLocation".into(), json!({ "uri": name }));
+    }
+
+    physical.insert(
+        "region".into(),
+        json!({
+            "startLine": start_line,
+            "startColumn": start_column,
+            "endLine": end_line,
+            "endColumn": end_column,
+        }),
+    );
+
+    let mut location = Map::new();
+    location.insert("physicalLocation".into(), Value::Object(physical));
+    if !label.message.is_empty() {
+        location.insert("message".into(), json!({ "text": label.message }));
+    }
+
+    Some(Value::Object(location))
+}
+
+/// Converts a diagnostic into a SARIF result.
+fn result(diagnostic: &Diagnostic<Source<'_>>) -> Value {
+    let source = diagnostic.source();
+    let primary = diagnostic.primary_label();
+    let locations: Vec<_> = primary
+        .and_then(|label| location(source, label))
+        .into_iter()
+        .collect();
+
+    let related_locations: Vec<_> = diagnostic
+        .labels()
+        .iter()
+        .filter(|label| !primary.is_some_and(|primary| std::ptr::eq(*label, primary)))
+        .filter_map(|label| location(source, label))
+        .collect();
+
+    let mut result = Map::new();
+    if let Some(code) = diagnostic.code() {
+        result.insert("ruleId".into(), json!(code));
+    }
+
+    result.insert("level".into(), json!(level(diagnostic.severity())));
+    result.insert("message".into(), json!({ "text": diagnostic.message() }));
+    result.insert("locations".into(), Value::Array(locations));
+    if !related_locations.is_empty() {
+        result.insert("relatedLocations".into(), Value::Array(related_locations));
+    }
+
+    Value::Object(result)
+}
+
+/// Converts the given diagnostics into a SARIF 2.1.0 log with a single run of
+/// the tool with the given name.
+///
+/// Each diagnostic becomes a result, located at its
+/// [primary label](Diagnostic::primary_label), with the other labels as
+/// related locations. The `ruleId` of a result is the
+/// [code](Diagnostic::with_code) of its diagnostic, if any. Columns are
+/// 1-based and counted in UTF-16 code units, as SARIF expects by default.
+///
+/// ```
+/// # use yumy::{Diagnostic, Label, Source, SourceSpan};
+/// # use serde_json::json;
+/// let source = Source::new("let x = y;", Some("src/main.rs"));
+/// let diagnostic = Diagnostic::error(source, SourceSpan::new(8, 9), "unknown variable")
+///     .with_code("E0425");
+///
+/// let log = yumy::sarif::to_sarif(&[diagnostic], "yumyc");
+/// assert_eq!(
+///     log["runs"][0]["results"][0],
+///     json!({
+///         "ruleId": "E0425",
+///         "level": "error",
+///         "message": { "text": "unknown variable" },
+///         "locations": [{
+///             "physicalLocation": {
+///                 "artifactLocation": { "uri": "src/main.rs" },
+///                 "region": { "startLine": 1, "startColumn": 9, "endLine": 1, "endColumn": 10 }
+///             }
+///         }]
+///     }),
+/// );
+///
+/// // a tab is a single code unit, no matter how wide it's displayed
+/// let source = Source::new("\tlet x = y;", None);
+/// let diagnostic = Diagnostic::error(source, SourceSpan::new(9, 10), "unknown variable")
+///     .with_label(Label::new(5..6, "declared here"));
+///
+/// let log = yumy::sarif::to_sarif(&[diagnostic], "yumyc");
+/// let result = &log["runs"][0]["results"][0];
+/// assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startColumn"], 10);
+/// assert_eq!(result["relatedLocations"][0]["physicalLocation"]["region"]["startColumn"], 6);
+/// ```
+pub fn to_sarif(diagnostics: &[Diagnostic<Source<'_>>], tool_name: &str) -> Value {
+    let results: Vec<_> = diagnostics.iter().map(result).collect();
+
+    json!({
+        "version": SARIF_VERSION,
+        "$schema": SARIF_SCHEMA,
+        "runs": [{
+            "tool": { "driver": { "name": tool_name } },
+            "results": results,
+        }],
+    })
+}
@@ -1,5 +1,13 @@
+use crate::text::{grapheme_width, WidthOptions};
 use nonmax::NonMaxU32;
 use owo_colors::Style;
+use std::{
+    borrow::Cow,
+    ops::Range,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Unit struct that represents the absence of
 /// a source in a diagnostic.
@@ -16,14 +24,40 @@ pub struct SourceSpan {
 impl SourceSpan {
     /// Creates a new source span. `start` and `end`
     /// are byte indexes into the source.
+    ///
+    /// # Panics
+    /// Panics if `end` is smaller than `start` or if `start` is `u32::MAX`,
+    /// which means sources can be at most `u32::MAX - 1` bytes long. See
+    /// [`SourceSpan::try_new`] for a fallible version.
     pub fn new(start: u32, end: u32) -> Self {
         assert!(end >= start);
         Self {
-            start: NonMaxU32::new(start).unwrap(),
+            start: NonMaxU32::new(start).expect("start is non-max"),
             end,
         }
     }
 
+    /// Creates a new source span, returning `None` if `end` is smaller than
+    /// `start` or if `start` is `u32::MAX`.
+    ///
+    /// ```
+    /// # use yumy::SourceSpan;
+    /// assert_eq!(SourceSpan::try_new(2, 5), Some(SourceSpan::new(2, 5)));
+    /// assert_eq!(SourceSpan::try_new(5, 2), None);
+    /// assert_eq!(SourceSpan::try_new(u32::MAX, u32::MAX), None);
+    /// ```
+    #[inline]
+    pub fn try_new(start: u32, end: u32) -> Option<Self> {
+        if end < start {
+            return None;
+        }
+
+        Some(Self {
+            start: NonMaxU32::new(start)?,
+            end,
+        })
+    }
+
     /// The start of this span. Inclusive.
     #[inline]
     pub fn start(&self) -> u32 {
@@ -42,69 +76,312 @@ impl SourceSpan {
         self.end - self.start()
     }
 
+    /// Is this span empty?
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Does this span contain the given value?
     #[inline]
     pub fn contains(&self, value: u32) -> bool {
         (self.start() <= value) && (value < self.end)
     }
+
+    /// Returns the smallest span that covers both this span and `other`.
+    ///
+    /// ```
+    /// # use yumy::SourceSpan;
+    /// let a = SourceSpan::new(2, 5);
+    /// assert_eq!(a.merge(SourceSpan::new(4, 8)), SourceSpan::new(2, 8));
+    /// assert_eq!(a.merge(SourceSpan::new(5, 6)), SourceSpan::new(2, 6));
+    /// assert_eq!(a.merge(SourceSpan::new(10, 12)), SourceSpan::new(2, 12));
+    /// ```
+    #[inline]
+    pub fn merge(self, other: SourceSpan) -> SourceSpan {
+        Self::new(self.start().min(other.start()), self.end.max(other.end))
+    }
+
+    /// Returns the overlap between this span and `other`, or `None` if they
+    /// don't overlap.
+    ///
+    /// ```
+    /// # use yumy::SourceSpan;
+    /// let a = SourceSpan::new(2, 5);
+    /// assert_eq!(a.intersect(SourceSpan::new(4, 8)), Some(SourceSpan::new(4, 5)));
+    /// assert_eq!(a.intersect(SourceSpan::new(5, 6)), None);
+    /// assert_eq!(a.intersect(SourceSpan::new(10, 12)), None);
+    /// ```
+    #[inline]
+    pub fn intersect(self, other: SourceSpan) -> Option<SourceSpan> {
+        let start = self.start().max(other.start());
+        let end = self.end.min(other.end);
+
+        (start < end).then(|| Self::new(start, end))
+    }
+
+    /// Returns the smallest span that covers all of the given spans, or
+    /// `None` if there are none.
+    ///
+    /// ```
+    /// # use yumy::SourceSpan;
+    /// let spans = [SourceSpan::new(4, 6), SourceSpan::new(10, 12), SourceSpan::new(1, 2)];
+    /// assert_eq!(SourceSpan::from_spans(spans), Some(SourceSpan::new(1, 12)));
+    /// assert_eq!(SourceSpan::from_spans([]), None);
+    /// ```
+    #[inline]
+    pub fn from_spans<I>(spans: I) -> Option<SourceSpan>
+    where
+        I: IntoIterator<Item = SourceSpan>,
+    {
+        spans.into_iter().reduce(Self::merge)
+    }
+}
+
+/// Creates a span from a range of byte indexes.
+///
+/// # Panics
+/// Panics if the range doesn't fit in a `u32`.
+///
+/// ```
+/// # use yumy::SourceSpan;
+/// assert_eq!(SourceSpan::from(3..7), SourceSpan::new(3, 7));
+/// ```
+impl From<Range<usize>> for SourceSpan {
+    #[inline]
+    fn from(value: Range<usize>) -> Self {
+        let start = u32::try_from(value.start).expect("span start should fit in a u32");
+        let end = u32::try_from(value.end).expect("span end should fit in a u32");
+
+        Self::new(start, end)
+    }
+}
+
+/// Creates a span from a `(start, end)` pair of byte indexes.
+///
+/// ```
+/// # use yumy::SourceSpan;
+/// assert_eq!(SourceSpan::from((3, 7)), SourceSpan::new(3, 7));
+/// ```
+impl From<(u32, u32)> for SourceSpan {
+    #[inline]
+    fn from((start, end): (u32, u32)) -> Self {
+        Self::new(start, end)
+    }
 }
 
+/// Collects spans into the smallest span that covers all of them, or `None`
+/// if there are none. See [`SourceSpan::from_spans`].
+///
+/// ```
+/// # use yumy::SourceSpan;
+/// let tokens = [SourceSpan::new(0, 3), SourceSpan::new(4, 9)];
+/// let span: Option<SourceSpan> = tokens.into_iter().collect();
+/// assert_eq!(span, Some(SourceSpan::new(0, 9)));
+/// ```
+impl FromIterator<SourceSpan> for Option<SourceSpan> {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = SourceSpan>,
+    {
+        SourceSpan::from_spans(iter)
+    }
+}
+
+/// A line of a [`Source`], without its line break.
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct SourceLine<'src> {
-    pub span: SourceSpan,
-    pub line: &'src str,
+pub struct SourceLine<'src> {
+    pub(crate) span: SourceSpan,
+    pub(crate) line: &'src str,
 }
 
 impl<'src> SourceLine<'src> {
-    pub fn new(line: &'src str, span: SourceSpan) -> Self {
+    pub(crate) fn new(line: &'src str, span: SourceSpan) -> Self {
         Self { span, line }
     }
+
+    /// The span of this line in the source.
+    #[inline]
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+
+    /// The text of this line, exactly as it is in the source. Lines are only
+    /// dedented when rendered, so this includes any leading whitespace.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("fn main() {\n    todo!()\n}", None);
+    /// let line = source.line(1).unwrap();
+    /// let span = line.span();
+    /// assert_eq!(line.text(), "    todo!()");
+    /// assert_eq!(line.text(), &source.src()[span.start() as usize..span.end() as usize]);
+    /// ```
+    #[inline]
+    pub fn text(&self) -> &'src str {
+        self.line
+    }
 }
 
 /// A source of text to use with a diagnostic.
+///
+/// Sources are equal if they have the same name and the same text, so they
+/// can be used to group diagnostics. The other settings of a source, such as
+/// its style or line offset, aren't compared.
+///
+/// Hashing only looks at the name and the length of the text, so it's cheap
+/// even for big sources. Comparing clones of a source is cheap too, as they
+/// share their text, but comparing distinct sources with the same name and
+/// length compares their whole text.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use yumy::{Diagnostic, Source};
+/// let main = Source::new("let x = y;", Some("main.rs"));
+/// let lib = Source::new("pub mod foo;", Some("lib.rs"));
+/// let diagnostics = [
+///     Diagnostic::error(main.clone(), 8..9, "unknown variable"),
+///     Diagnostic::error(lib, 8..11, "file not found"),
+///     Diagnostic::warning(main, 4..5, "unused variable"),
+/// ];
+///
+/// let mut by_source: HashMap<&Source, Vec<&Diagnostic<Source>>> = HashMap::new();
+/// for diagnostic in &diagnostics {
+///     by_source.entry(diagnostic.source()).or_default().push(diagnostic);
+/// }
+///
+/// assert_eq!(by_source.len(), 2);
+/// assert_eq!(by_source[&Source::new("let x = y;", Some("main.rs"))].len(), 2);
+/// ```
 #[derive(Debug, Clone)]
 pub struct Source<'src> {
     src: &'src str,
     name: Option<&'src str>,
+    path: Option<&'src Path>,
     style: Option<Style>,
-    lines: Vec<SourceLine<'src>>,
+    line_offset: usize,
+    /// The byte offsets at which each line of the source starts, computed
+    /// when first needed. Shared between clones, so they're computed only
+    /// once for all of them.
+    line_starts: Arc<OnceLock<Vec<u32>>>,
+}
+
+impl PartialEq for Source<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // sources are usually compared against copies of themselves, which
+        // share the text
+        let same_src = std::ptr::eq(self.src, other.src) || self.src == other.src;
+        self.name == other.name && same_src
+    }
+}
+
+impl Eq for Source<'_> {}
+
+impl std::hash::Hash for Source<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // the text is left out so that hashing big sources is cheap. equal
+        // sources have the same length, so this is still consistent with eq
+        self.name.hash(state);
+        self.src.len().hash(state);
+    }
 }
 
 impl<'src> Source<'src> {
-    fn lines(src: &str) -> Vec<SourceLine> {
-        let line_starts = std::iter::once(0).chain(src.match_indices('\n').map(|(i, _)| i + 1));
-        let line_spans =
-            line_starts
-                .zip(src.lines().map(|x| (x, x.len())))
-                .map(|(start, (line, len))| {
-                    (line, SourceSpan::new(start as u32, (start + len) as u32))
-                });
-
-        line_spans
-            .map(|(line, span)| SourceLine::new(line, span))
+    fn compute_line_starts(src: &str) -> Vec<u32> {
+        let len = src.len() as u32;
+        std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            // a line break at the end of the source doesn't start a new line,
+            // but an empty source still has an (empty) line
+            .filter(|&start| start == 0 || start < len)
             .collect()
     }
 
-    /// Creates a new source.
+    /// Returns the byte offsets at which each line of this source starts,
+    /// computing them if needed.
+    #[inline]
+    fn line_starts(&self) -> &[u32] {
+        self.line_starts
+            .get_or_init(|| Self::compute_line_starts(self.src))
+    }
+
+    /// Creates a new source. The lines of the source are only computed once
+    /// they're needed, so creating a source is cheap. Clones of a source
+    /// share its lines, so cloning it is cheap as well.
     pub fn new(src: &'src str, name: Option<&'src str>) -> Self {
         Self {
             src,
             name,
+            path: None,
             style: None,
-            lines: Self::lines(src),
+            line_offset: 0,
+            line_starts: Arc::default(),
         }
     }
 
+    /// Returns whether this source shares its lines with the given one,
+    /// which is the case for clones of the same source. The lines are only
+    /// computed once for all of the sources that share them.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("let x = 5;", Some("main.rs"));
+    /// assert!(source.shares_lines_with(&source.clone()));
+    ///
+    /// let other = Source::new("let x = 5;", Some("main.rs"));
+    /// assert_eq!(source, other);
+    /// assert!(!source.shares_lines_with(&other));
+    /// ```
+    #[inline]
+    pub fn shares_lines_with(&self, other: &Source<'_>) -> bool {
+        Arc::ptr_eq(&self.line_starts, &other.line_starts)
+    }
+
     /// Creates a new source with the given style.
     pub fn styled(src: &'src str, name: Option<&'src str>, style: Style) -> Self {
         Self {
             src,
             name,
+            path: None,
             style: Some(style),
-            lines: Self::lines(src),
+            line_offset: 0,
+            line_starts: Arc::default(),
         }
     }
 
+    /// Sets the number of lines that come before this source. Useful when
+    /// the source is a snippet of a bigger file, so that the line numbers
+    /// shown match the ones in the original file.
+    #[inline(always)]
+    pub fn with_line_offset(mut self, offset: usize) -> Self {
+        self.line_offset = offset;
+        self
+    }
+
+    /// Sets the path of the file this source was loaded from. When
+    /// hyperlinks are enabled (see
+    /// [`Config::hyperlinks`](crate::Config::hyperlinks)), the name of the
+    /// source is rendered as a link to the file.
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// # use yumy::{Config, Diagnostic, HyperlinkChoice, Source};
+    /// let source = Source::new("let x = y;", Some("main.rs"))
+    ///     .with_path(Path::new("/home/user/project/main.rs"));
+    /// let diagnostic = Diagnostic::error(source, 8..9, "unknown variable");
+    ///
+    /// let config = Config::default().with_hyperlinks(HyperlinkChoice::Always);
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &config).unwrap();
+    /// assert!(rendered.contains("\x1b]8;;file:///home/user/project/main.rs#L1\x1b\\"));
+    /// ```
+    #[inline(always)]
+    pub fn with_path(mut self, path: &'src Path) -> Self {
+        self.path = Some(path);
+        self
+    }
+
     pub fn src(&self) -> &'src str {
         self.src
     }
@@ -113,26 +390,379 @@ impl<'src> Source<'src> {
         self.name
     }
 
+    pub fn path(&self) -> Option<&'src Path> {
+        self.path
+    }
+
     pub fn style(&self) -> Option<Style> {
         self.style
     }
 
-    pub(crate) fn line(&self, index: u32) -> Option<SourceLine<'src>> {
-        self.lines.get(index as usize).copied()
+    pub fn line_offset(&self) -> usize {
+        self.line_offset
+    }
+
+    /// Returns a `file://` URL to the given line of the file this source was
+    /// loaded from, if known.
+    pub(crate) fn file_url(&self, line: Option<usize>) -> Option<String> {
+        let path = self.path?;
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let path = path.to_string_lossy();
+        let path = match std::path::MAIN_SEPARATOR {
+            '/' => path,
+            separator => Cow::Owned(path.replace(separator, "/")),
+        };
+
+        let mut url = String::from("file://");
+        if !path.starts_with('/') {
+            url.push('/');
+        }
+
+        for byte in path.bytes() {
+            match byte {
+                b'A'..=b'Z'
+                | b'a'..=b'z'
+                | b'0'..=b'9'
+                | b'/'
+                | b'-'
+                | b'_'
+                | b'.'
+                | b'~'
+                | b':' => url.push(byte as char),
+                _ => url.push_str(&format!("%{byte:02X}")),
+            }
+        }
+
+        if let Some(line) = line {
+            url.push_str(&format!("#L{line}"));
+        }
+
+        Some(url)
+    }
+
+    /// Returns the line number (1-based, including the line offset) of the
+    /// line with the given index.
+    #[inline]
+    pub(crate) fn line_number(&self, index: u32) -> usize {
+        index as usize + 1 + self.line_offset
+    }
+
+    /// Returns the position of the given byte as a 1-based line number
+    /// (including the line offset) and a 1-based display column. Bytes of a
+    /// line break, as well as the end of the source, are positioned right
+    /// after the end of their line.
+    ///
+    /// Columns are measured with the default [`WidthOptions`], so tabs are
+    /// [`TAB_WIDTH`](crate::text::TAB_WIDTH) columns wide. See
+    /// [`Source::position_at_with`] to measure them differently.
+    #[inline]
+    pub fn position_at(&self, byte_index: u32) -> Option<(usize, usize)> {
+        self.position_at_with(byte_index, WidthOptions::default())
+    }
+
+    /// Returns the position of the given byte like [`Source::position_at`],
+    /// measuring columns with the given [`WidthOptions`].
+    ///
+    /// ```
+    /// # use yumy::{text::WidthOptions, Source};
+    /// let source = Source::new("\tlet x;", None);
+    /// assert_eq!(source.position_at(1), Some((1, 5)));
+    ///
+    /// let options = WidthOptions {
+    ///     tab_width: 8,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(source.position_at_with(1, options), Some((1, 9)));
+    /// ```
+    pub fn position_at_with(
+        &self,
+        byte_index: u32,
+        options: WidthOptions,
+    ) -> Option<(usize, usize)> {
+        let line_index = self.line_index_of_byte(byte_index)?;
+        let line = self.line(line_index)?;
+        // line breaks are positioned right after the end of their line
+        let end = (byte_index - line.span.start()).min(line.span.len());
+        let before = line.line.get(..end as usize)?;
+
+        Some((
+            self.line_number(line_index),
+            options.display_width(before) + 1,
+        ))
+    }
+
+    /// Returns the position right after the end of the given span as a 1-based
+    /// line number (including the line offset) and a 1-based display column.
+    /// Unlike [`Source::position_at`], a span ending with a line break ends on
+    /// the line it breaks, not on the next one.
+    pub(crate) fn end_position_of(&self, span: SourceSpan) -> Option<(usize, usize)> {
+        let last_byte = span.end().saturating_sub(1).max(span.start());
+        let line_index = self.line_index_of_byte(last_byte)?;
+        let line = self.line(line_index)?;
+        let end = (span.end() - line.span.start()).min(line.span.len());
+        let before = line.line.get(..end as usize)?;
+
+        Some((
+            self.line_number(line_index),
+            WidthOptions::default().display_width(before) + 1,
+        ))
+    }
+
+    /// Returns the byte at the given 1-based line number (including the line
+    /// offset) and 1-based display column. This is the inverse of
+    /// [`Source::position_at`].
+    ///
+    /// Columns in the middle of a wide grapheme snap to the start of it, and
+    /// columns past the end of the line return the end of the line.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("let x = 1;\nlet 界 = 2;", None);
+    /// assert_eq!(source.byte_at_display_col(2, 5), Some(15));
+    /// // the second column of the wide character
+    /// assert_eq!(source.byte_at_display_col(2, 6), Some(15));
+    /// assert_eq!(source.byte_at_display_col(2, 7), Some(18));
+    /// // past the end of the line
+    /// assert_eq!(source.byte_at_display_col(1, 80), Some(10));
+    /// assert_eq!(source.byte_at_display_col(3, 1), None);
+    /// ```
+    pub fn byte_at_display_col(&self, line: usize, col: usize) -> Option<u32> {
+        let index = line.checked_sub(1 + self.line_offset)?;
+        let line = self.line(u32::try_from(index).ok()?)?;
+        let target = col.checked_sub(1)?;
+
+        let mut width = 0;
+        for (offset, grapheme) in line.line.grapheme_indices(true) {
+            width += grapheme_width(grapheme);
+            if width > target {
+                return Some(line.span.start() + offset as u32);
+            }
+        }
+
+        Some(line.span.end())
+    }
+
+    /// Returns the span covering the graphemes with the given (0-based,
+    /// end exclusive) indices of the line with the given 1-based line number
+    /// (including the line offset), or `None` if they're out of bounds.
+    ///
+    /// Spans returned by this method always start and end at grapheme
+    /// boundaries, so they're safe to use for labels.
+    ///
+    /// ```
+    /// # use yumy::{Source, SourceSpan};
+    /// let source = Source::new("fn main() {\n    \"café 👩\u{200D}💻\"\n}", None);
+    /// // `é` and the emoji sequence are a single grapheme each
+    /// let span = source.grapheme_span(2, 5, 11).unwrap();
+    /// assert_eq!(&source.src()[span.start() as usize..span.end() as usize], "café 👩\u{200D}💻");
+    /// assert_eq!(source.grapheme_span(2, 11, 12), Some(SourceSpan::new(34, 35)));
+    /// assert_eq!(source.grapheme_span(2, 11, 13), None);
+    /// assert_eq!(source.grapheme_span(4, 0, 0), None);
+    /// ```
+    pub fn grapheme_span(
+        &self,
+        line: usize,
+        start_grapheme: usize,
+        end_grapheme: usize,
+    ) -> Option<SourceSpan> {
+        if start_grapheme > end_grapheme {
+            return None;
+        }
+
+        let index = line.checked_sub(1 + self.line_offset)?;
+        let line = self.line(u32::try_from(index).ok()?)?;
+
+        // byte offsets of each grapheme boundary, including the end of the line
+        let mut boundaries = line
+            .line
+            .grapheme_indices(true)
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(line.line.len()));
+
+        let start = boundaries.nth(start_grapheme)?;
+        let end = if end_grapheme == start_grapheme {
+            start
+        } else {
+            boundaries.nth(end_grapheme - start_grapheme - 1)?
+        };
+
+        let line_start = line.span.start();
+        Some(SourceSpan::new(
+            line_start + start as u32,
+            line_start + end as u32,
+        ))
+    }
+
+    /// Returns the position of the given byte as a 0-based line number
+    /// (including the line offset) and a 0-based column in UTF-16 code units,
+    /// as used by editor protocols such as LSP. Characters outside of the
+    /// basic multilingual plane take two code units, regardless of their
+    /// display width.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("fn main() {\n    \"🦀\" + \"界\"\n}", None);
+    /// let crab = source.src().find('🦀').unwrap() as u32;
+    /// let world = source.src().find('界').unwrap() as u32;
+    /// // surrogate pair: 2 code units, 4 bytes
+    /// assert_eq!(source.utf16_position_at(crab + 4), Some((1, 7)));
+    /// // CJK: 1 code unit, 3 bytes
+    /// assert_eq!(source.utf16_position_at(world + 3), Some((1, 13)));
+    /// // the display column differs
+    /// assert_eq!(source.position_at(world + 3), Some((2, 15)));
+    /// ```
+    pub fn utf16_position_at(&self, byte_index: u32) -> Option<(usize, usize)> {
+        let line_index = self.line_index_of_byte(byte_index)?;
+        let line = self.line(line_index)?;
+        let end = (byte_index - line.span.start()).min(line.span.len());
+        let before = line.line.get(..end as usize)?;
+
+        Some((
+            self.line_number(line_index) - 1,
+            before.encode_utf16().count(),
+        ))
+    }
+
+    /// Returns the number of lines in this source. A line break at the end of
+    /// the source doesn't start a new line, and an empty source has a single
+    /// empty line.
+    ///
+    /// ```
+    /// # use yumy::{ColorChoice, Config, Diagnostic, Source, SourceSpan};
+    /// let source = Source::new("let x = 1;\nlet y = 2;\n", None);
+    /// assert_eq!(source.line_count(), 2);
+    /// assert_eq!(Source::new("", None).line_count(), 1);
+    ///
+    /// // the end of the source belongs to the last line either way
+    /// let with_newline = Source::new("a\nb\n", None);
+    /// let without_newline = Source::new("a\nb", None);
+    /// assert_eq!(with_newline.line_count(), without_newline.line_count());
+    /// assert_eq!(with_newline.position_at(4), Some((2, 2)));
+    /// assert_eq!(without_newline.position_at(3), Some((2, 2)));
+    /// assert_eq!(with_newline.line_range_of_span(SourceSpan::new(4, 4)), Some(1..2));
+    /// assert_eq!(without_newline.line_range_of_span(SourceSpan::new(3, 3)), Some(1..2));
+    ///
+    /// let render = |source| {
+    ///     let mut rendered = String::new();
+    ///     let config = Config::default().with_color(ColorChoice::Never);
+    ///     Diagnostic::error(source, 2..3, "here")
+    ///         .write_to_fmt(&mut rendered, &config)
+    ///         .unwrap();
+    ///     rendered
+    /// };
+    /// assert_eq!(render(with_newline.clone()), render(without_newline));
+    /// assert!(render(with_newline).ends_with("2 │  b\n  :  ^\n\n"));
+    /// ```
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_starts().len()
+    }
+
+    /// Returns the line with the given (0-based) index.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("let x = 1;\nlet y = 2;\n", None);
+    /// let line = source.line(1).unwrap();
+    /// assert_eq!(line.text(), "let y = 2;");
+    /// assert_eq!(line.span().start(), 11);
+    /// assert!(source.line(2).is_none());
+    /// ```
+    pub fn line(&self, index: u32) -> Option<SourceLine<'src>> {
+        let line_starts = self.line_starts();
+        let start = *line_starts.get(index as usize)?;
+        let next_start = line_starts
+            .get(index as usize + 1)
+            .map_or(self.src.len() as u32, |&next| next);
+
+        let mut line = &self.src[start as usize..next_start as usize];
+        if let Some(stripped) = line.strip_suffix('\n') {
+            line = stripped.strip_suffix('\r').unwrap_or(stripped);
+        }
+
+        let span = SourceSpan::new(start, start + line.len() as u32);
+        Some(SourceLine::new(line, span))
+    }
+
+    /// Returns the text of the line with the given (0-based) index, without
+    /// its line break.
+    ///
+    /// ```
+    /// # use yumy::Source;
+    /// let source = Source::new("fn main() {\n    todo!()\n}", None);
+    /// assert_eq!(source.line_text(1), Some("    todo!()"));
+    /// assert_eq!(source.line_text(3), None);
+    /// ```
+    #[inline]
+    pub fn line_text(&self, index: u32) -> Option<&'src str> {
+        self.line(index).map(|line| line.line)
     }
 
+    /// Returns the range of (0-based) indices of the lines covered by the
+    /// given span, or `None` if it's out of bounds. A span ending right after
+    /// a line break doesn't cover the next line, and empty spans cover the
+    /// line they're in.
+    ///
+    /// ```
+    /// # use yumy::{Source, SourceSpan};
+    /// let source = Source::new("let x = 1;\nlet y = 2;\n", None);
+    /// // ends at the line break, without including it
+    /// assert_eq!(source.line_range_of_span(SourceSpan::new(4, 10)), Some(0..1));
+    /// // ends right after the line break
+    /// assert_eq!(source.line_range_of_span(SourceSpan::new(4, 11)), Some(0..1));
+    /// // ends after the first byte of the next line
+    /// assert_eq!(source.line_range_of_span(SourceSpan::new(4, 12)), Some(0..2));
+    /// // empty span at the start of a line
+    /// assert_eq!(source.line_range_of_span(SourceSpan::new(11, 11)), Some(1..2));
+    /// assert_eq!(source.line_range_of_span(SourceSpan::new(4, 30)), None);
+    /// ```
+    ///
+    /// A span covering the whole source covers all of its lines, and is
+    /// rendered as a multiline label from the first line to the last:
+    /// ```
+    /// # use yumy::{Config, Diagnostic, Label, Source, SourceSpan};
+    /// let src = "fn main() {\n    let x = 5;\n}\n";
+    /// let source = Source::new(src, None);
+    /// let whole = SourceSpan::new(0, src.len() as u32);
+    /// assert_eq!(source.line_range_of_span(whole), Some(0..3));
+    ///
+    /// let diagnostic = Diagnostic::new("error: unused file")
+    ///     .with_source(source)
+    ///     .with_label(Label::new(whole, "this file"))
+    ///     .with_label(Label::new(16..17, "this variable"));
+    ///
+    /// let mut rendered = String::new();
+    /// diagnostic.write_to_fmt(&mut rendered, &Config::plain()).unwrap();
+    /// assert!(rendered.ends_with(
+    ///     "1 │ ┬ fn main() {\n\
+    ///      2 │ │     let x = 5;\n  \
+    ///      : │     ^ this variable\n\
+    ///      3 │ ┼ }\n  \
+    ///      : ╰╶╶ this file\n\
+    ///      \n"
+    /// ));
+    /// ```
+    pub fn line_range_of_span(&self, span: SourceSpan) -> Option<Range<u32>> {
+        let start = self.line_index_of_byte(span.start())?;
+        let end = self.line_index_of_byte(span.end().saturating_sub(1).max(span.start()))?;
+        self.line_index_of_byte(span.end())?;
+
+        Some(start..(end + 1))
+    }
+
+    /// Returns the index of the line that contains the given byte. Line breaks
+    /// belong to the line they end, and the end of the source belongs to the
+    /// last line.
     pub(crate) fn line_index_of_byte(&self, byte_index: u32) -> Option<u32> {
         if byte_index > self.src.len() as u32 {
             return None;
         }
 
-        // TODO: change to a binary search
-        for (index, line) in self.lines.iter().enumerate() {
-            if line.span.contains(byte_index) {
-                return Some(index as u32);
-            }
-        }
+        let index = self
+            .line_starts()
+            .partition_point(|&start| start <= byte_index)
+            .checked_sub(1)?;
 
-        None
+        Some(index as u32)
     }
 }
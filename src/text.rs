@@ -0,0 +1,166 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The default width of a tab, in columns.
+pub const TAB_WIDTH: usize = 4;
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+const SKIN_TONE_MODIFIERS: std::ops::RangeInclusive<char> = '\u{1F3FB}'..='\u{1F3FF}';
+
+/// A function that overrides the width of a grapheme cluster. Returning
+/// `None` means the default width should be used.
+pub type WidthOverride = fn(&str) -> Option<usize>;
+
+/// Options that control how the width of text is measured.
+#[derive(Debug, Clone, Copy)]
+pub struct WidthOptions {
+    /// Function consulted before computing the default width of a grapheme
+    /// cluster.
+    pub width_override: Option<WidthOverride>,
+    /// Whether characters with an ambiguous East Asian width should be
+    /// considered wide, as they are in CJK contexts.
+    pub east_asian_wide: bool,
+    /// The width of a tab, in columns.
+    pub tab_width: usize,
+}
+
+impl Default for WidthOptions {
+    fn default() -> Self {
+        Self {
+            width_override: None,
+            east_asian_wide: false,
+            tab_width: TAB_WIDTH,
+        }
+    }
+}
+
+impl WidthOptions {
+    /// Returns the display width of a single grapheme cluster, in columns,
+    /// according to these options. See [`grapheme_width`].
+    pub fn grapheme_width(&self, grapheme: &str) -> usize {
+        if let Some(width) = self.width_override.and_then(|f| f(grapheme)) {
+            return width;
+        }
+
+        if grapheme == "\t" {
+            return self.tab_width;
+        }
+
+        let is_emoji_sequence = grapheme.chars().any(|c| {
+            c == ZERO_WIDTH_JOINER
+                || c == EMOJI_PRESENTATION_SELECTOR
+                || SKIN_TONE_MODIFIERS.contains(&c)
+        });
+
+        if is_emoji_sequence {
+            2
+        } else if self.east_asian_wide {
+            grapheme.width_cjk()
+        } else {
+            grapheme.width()
+        }
+    }
+
+    /// Returns the display width of the given text, in columns, according to
+    /// these options. See [`display_width`].
+    pub fn display_width(&self, text: &str) -> usize {
+        // every ascii character is its own grapheme (except for "\r\n") with
+        // a width of 1, so ascii text can be measured without segmenting it
+        if self.width_override.is_none() && text.is_ascii() {
+            let mut width = 0;
+            let mut previous = 0;
+            for b in text.bytes() {
+                width += match b {
+                    b'\t' => self.tab_width,
+                    b'\n' if previous == b'\r' => {
+                        width -= 1;
+                        0
+                    }
+                    b'\n' => 0,
+                    _ => 1,
+                };
+                previous = b;
+            }
+
+            return width;
+        }
+
+        text.graphemes(true).map(|g| self.grapheme_width(g)).sum()
+    }
+}
+
+/// Returns whether the given character belongs to a right-to-left script,
+/// such as Arabic or Hebrew, or is a character that forces right-to-left
+/// ordering.
+///
+/// # Example
+/// ```
+/// use yumy::text::is_rtl;
+///
+/// assert!(is_rtl('ب'));
+/// assert!(is_rtl('א'));
+/// assert!(!is_rtl('a'));
+/// ```
+pub fn is_rtl(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+            | '\u{200F}'
+            | '\u{202B}'
+            | '\u{202E}'
+            | '\u{2067}'
+    )
+}
+
+/// Returns whether the given text contains any right-to-left characters. See
+/// [`is_rtl`].
+#[inline]
+pub fn contains_rtl(text: &str) -> bool {
+    !text.is_ascii() && text.chars().any(is_rtl)
+}
+
+/// Returns the display width of a single grapheme cluster, in columns.
+///
+/// Emoji sequences (joined with ZWJ, with an emoji presentation selector or
+/// with a skin tone modifier) are rendered as a single wide character by most
+/// terminals, so they are considered to have a width of 2. Tabs have a width
+/// of [`TAB_WIDTH`].
+///
+/// # Example
+/// ```
+/// use yumy::text::grapheme_width;
+///
+/// assert_eq!(grapheme_width("a"), 1);
+/// assert_eq!(grapheme_width("\t"), 4);
+/// assert_eq!(grapheme_width("界"), 2);
+/// // woman + ZWJ + laptop
+/// assert_eq!(grapheme_width("👩\u{200D}💻"), 2);
+/// // thumbs up + skin tone modifier
+/// assert_eq!(grapheme_width("👍\u{1F3FD}"), 2);
+/// ```
+#[inline]
+pub fn grapheme_width(grapheme: &str) -> usize {
+    WidthOptions::default().grapheme_width(grapheme)
+}
+
+/// Returns the display width of the given text, in columns. This is the sum
+/// of the [`grapheme_width`] of each grapheme cluster in it.
+///
+/// # Example
+/// ```
+/// use yumy::text::display_width;
+///
+/// assert_eq!(display_width("let x = 5;"), 10);
+/// assert_eq!(display_width("\tfoo"), 7);
+/// assert_eq!(display_width("こんにちは"), 10);
+/// assert_eq!(display_width("👨\u{200D}👩\u{200D}👧!"), 3);
+/// ```
+#[inline]
+pub fn display_width(text: &str) -> usize {
+    WidthOptions::default().display_width(text)
+}
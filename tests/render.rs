@@ -0,0 +1,982 @@
+use std::borrow::Cow;
+use yumy::owo_colors::Style;
+use yumy::{
+    CarriageReturn, Charset, ColorChoice, Config, DedentMode, DefaultStyles, Diagnostic, Footnote,
+    HeaderStyle, Label, LineNumberAlign, Localization, Severity, Source, SourceSpan,
+};
+
+fn render(diagnostic: &Diagnostic<Source>, config: &Config) -> String {
+    let mut rendered = String::new();
+    diagnostic.write_to_fmt(&mut rendered, config).unwrap();
+    rendered
+}
+
+#[test]
+fn extra_spans_use_a_single_label_slot() {
+    let diagnostic = Diagnostic::new("error: mismatched delimiters")
+        .with_source(Source::new("let v = (1, 2]; let w = x;", None))
+        .with_label(Label::new(8..9, "these don't match").with_extra_span(13..14))
+        .with_label(Label::new(24..25, "not found"));
+
+    assert_eq!(
+        render(&diagnostic, &Config::plain().with_max_labels(Some(2))),
+        "error: mismatched delimiters\n  \
+         @ [unknown]\n\
+         1 │  let v = (1, 2]; let w = x;\n  \
+         :          ^    ^ these don't match\n  \
+         :                          ^ not found\n\
+         \n",
+    );
+    assert_eq!(
+        render(&diagnostic, &Config::plain().with_max_labels(Some(1))),
+        "error: mismatched delimiters\n  \
+         @ [unknown]\n\
+         1 │  let v = (1, 2]; let w = x;\n  \
+         :          ^    ^ these don't match\n  \
+         : … and 1 more label\n\
+         \n",
+    );
+}
+
+#[test]
+fn multiline_labels_ignore_insertion_order() {
+    let src = "fn main() {\n    let x = (1,\n        2);\n}";
+    let block = Label::new(10..37, "this block");
+    let tuple = Label::new(10..36, "this tuple");
+
+    let with_labels = |labels: Vec<Label>| {
+        let diagnostic = Diagnostic::new("error: mismatched types")
+            .with_source(Source::new(src, None))
+            .with_labels(labels);
+
+        render(&diagnostic, &Config::plain())
+    };
+
+    assert_eq!(
+        with_labels(vec![block.clone(), tuple.clone()]),
+        with_labels(vec![tuple, block]),
+    );
+}
+
+#[test]
+fn multiline_labels_reuse_slots() {
+    let labels = (0..5).map(|i| Label::new(i * 2..i * 2 + 3, format!("label {i}")));
+    let diagnostic = Diagnostic::new("error: interleaved labels")
+        .with_source(Source::new("a\nb\nc\nd\ne\nf\n", None))
+        .with_labels(labels.collect());
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.ends_with(
+        "1 │ ┬  a\n\
+         2 │ ┼┬ b\n  \
+         : ╰┼╶╶ label 0\n\
+         3 │ ┬┼ c\n  \
+         : │╰╶╶ label 1\n\
+         4 │ ┼┬ d\n  \
+         : ╰┼╶╶ label 2\n\
+         5 │ ┬┼ e\n  \
+         : │╰╶╶ label 3\n\
+         6 │ ┼  f\n  \
+         : ╰╶╶╶ label 4\n\
+         \n"
+    ));
+}
+
+#[test]
+fn multiline_label_messages() {
+    let diagnostic = Diagnostic::new("error: unclosed block")
+        .with_source(Source::new("fn main() {\n    let x = y;", None))
+        .with_label(Label::new(10..26, "this block\nis never closed"))
+        .with_label(Label::new(24..25, "not found\nin this scope"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains(
+        "2 │ ┼     let x = y;\n  \
+         : ┼             ^ not found\n  \
+         : │               in this scope\n  \
+         : ╰╶╶╶╶╶╶╶╶╶╶╶╶╶╶╶ this block\n  \
+         :                  is never closed\n"
+    ));
+}
+
+#[test]
+fn label_order() {
+    let diagnostic = Diagnostic::new("error: mismatched types")
+        .with_source(Source::new("let x: u32 = \"five\";", None))
+        .with_label(Label::new(13..19, "so this is wrong").with_order(1))
+        .with_label(Label::new(7..10, "expected because of this"));
+
+    let config = Config::default().with_color(ColorChoice::Never);
+    let rendered = render(&diagnostic, &config);
+
+    let expected = rendered.find("expected because of this").unwrap();
+    let conclusion = rendered.find("so this is wrong").unwrap();
+    assert!(expected < conclusion);
+}
+
+#[test]
+fn label_note() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found").with_note("did you mean `x`?"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n  \
+         :            did you mean `x`?\n\
+         \n",
+    );
+}
+
+#[test]
+fn footnote_kinds() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found"))
+        .with_note("variables must be declared before use")
+        .with_help("did you mean `x`?")
+        .with_footnote(Footnote::info("1 error found"))
+        .with_footnote(Footnote::note("shadowing is allowed"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n  \
+         > variables must be declared before use\n  \
+         ? did you mean `x`?\n  \
+         i 1 error found\n  \
+         > shadowing is allowed\n\
+         \n",
+    );
+}
+
+#[test]
+fn footnotes_align_with_gutter() {
+    let config = Config::default().with_color(ColorChoice::Never);
+    for (lines, gutter) in [(1, "1 │"), (12, "12 │"), (123, "123 │")] {
+        let src = "x\n".repeat(lines);
+        let last = (lines - 1) * 2;
+        let diagnostic = Diagnostic::new("error: unexpected `x`")
+            .with_source(Source::new(&src, None))
+            .with_label(Label::new(last..last + 1, "here"))
+            .with_note("first line\nsecond line");
+
+        let rendered = render(&diagnostic, &config);
+
+        let padding = " ".repeat(gutter.len() - "│".len() - 1);
+        assert!(rendered.contains(&format!("{gutter}  x\n")));
+        assert!(rendered.contains(&format!(
+            "\n{padding} > first line\n{padding}   second line\n"
+        )));
+    }
+}
+
+#[test]
+fn multiline_footnote_messages() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found"))
+        .with_help("declare it first:\nlet y = 0;");
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.ends_with(
+        "  :          ^ not found\n  \
+         ? declare it first:\n    \
+         let y = 0;\n\
+         \n"
+    ));
+
+    let mut rendered = Vec::new();
+    diagnostic
+        .write_to_compact(&mut rendered, &Config::plain())
+        .unwrap();
+    assert!(String::from_utf8(rendered).unwrap().ends_with(
+        "│ [line 1]: not found\n\
+         ? declare it first:\n  \
+         let y = 0;\n\
+         \n"
+    ));
+}
+
+#[test]
+fn footnote_references() {
+    let diagnostic = Diagnostic::new("error: mismatched types")
+        .with_source(Source::new("let x: u32 = \"five\";", None))
+        .with_label(Label::new(7..10, "expected `u32`"))
+        .with_label(Label::new(13..19, "found `&str`"))
+        .with_footnote(Footnote::new("").referencing(0))
+        .with_footnote(Footnote::help("parse it first").referencing(1));
+
+    let config = Config::plain().with_number_labels(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("\n  > note: see (1)\n  ? help: see (2): parse it first\n"));
+
+    // references are only shown along with the numbers of the labels
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains("\n  > \n  ? parse it first\n"));
+}
+
+#[test]
+fn boxed_charset() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found").with_note("did you mean `x`?"))
+        .with_help("declare it first");
+
+    let config = Config::plain().with_charset(Charset::boxed());
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 ╭  let x = y;\n  \
+         :          ^ not found\n  \
+         ╰            did you mean `x`?\n  \
+         ? declare it first\n\
+         \n",
+    );
+}
+
+#[test]
+fn squiggly_charset() {
+    let diagnostic = Diagnostic::new("warning: unused variable")
+        .with_source(Source::new("let value = 5;", Some("main.rs")))
+        .with_label(Label::new(4..9, "never read"));
+
+    let config = Config::plain().with_charset(Charset::squiggly());
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "warning: unused variable\n  \
+         @ [main.rs]\n\
+         1 │  let value = 5;\n  \
+         :      ^~~~~ never read\n\
+         \n",
+    );
+}
+
+#[test]
+fn style_presets() {
+    let diagnostic = Diagnostic::new("warning: unused variable")
+        .with_source(Source::new("let value = 5;", Some("main.rs")))
+        .with_label(Label::new(4..9, "never read"));
+
+    let with_styles = |styles| render(&diagnostic, &Config::default().with_styles(styles));
+
+    let rendered = with_styles(DefaultStyles::monochrome());
+    assert!(rendered.contains("\x1b[1m1\x1b[0m \x1b[1m│\x1b[0m  let value = 5;\n"));
+    assert!(rendered.contains(&format!("{} never read\n", "\x1b[1m^\x1b[0m".repeat(5))));
+
+    let rendered = with_styles(DefaultStyles::high_contrast());
+    assert!(
+        rendered.contains("\x1b[96;1m1\x1b[0m \x1b[96;1m│\x1b[0m  \x1b[97mlet value = 5;\x1b[0m\n")
+    );
+    assert!(rendered.contains(&format!("{} never read\n", "\x1b[93;1m^\x1b[0m".repeat(5))));
+
+    let rendered = with_styles(DefaultStyles::dimmed());
+    assert!(rendered
+        .contains("\x1b[34;2m1\x1b[0m \x1b[34;2m│\x1b[0m  \x1b[37;2mlet value = 5;\x1b[0m\n"));
+    assert!(rendered.contains(&format!("{} never read\n", "\x1b[33;2m^\x1b[0m".repeat(5))));
+}
+
+#[test]
+fn severity_style() {
+    let source = Source::new("let value = 5;", Some("main.rs"));
+    let styles = DefaultStyles {
+        warning: Style::new().magenta(),
+        ..Default::default()
+    };
+
+    let diagnostic = Diagnostic::warning(source, 4..9, "unused variable");
+    let config = Config::default()
+        .with_color(ColorChoice::Always)
+        .with_styles(styles);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.starts_with("\x1b[35mwarning\x1b[0m: unused variable\n"));
+}
+
+#[test]
+fn styles_for_severity() {
+    let diagnostic = Diagnostic::new("unused variable")
+        .with_severity(Severity::Warning)
+        .with_source(Source::new("let x = 5;", None))
+        .with_label(Label::new(4..5, "never read"));
+
+    let config = Config::default()
+        .with_color(ColorChoice::Always)
+        .with_styles(DefaultStyles::for_severity(Severity::Warning));
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("\x1b[33m^\x1b[0m"));
+}
+
+#[test]
+fn line_number_align() {
+    let src = "a\n".repeat(8) + "let x = 5;\nlet y = x;\n";
+    let diagnostic = Diagnostic::new("warning: unused variable")
+        .with_source(Source::new(&src, Some("main.rs")))
+        .with_label(Label::new(6..7, "here"))
+        .with_label(Label::new(31..32, "and here"))
+        .with_note("declared twice");
+
+    let with_align = |align| render(&diagnostic, &Config::plain().with_line_number_align(align));
+
+    assert_eq!(
+        with_align(LineNumberAlign::Left),
+        "warning: unused variable\n   \
+         @ [main.rs]\n\
+         4  │  a\n   \
+         :  ^ here\n\
+         10 │  let y = x;\n   \
+         :      ^ and here\n   \
+         > declared twice\n\
+         \n",
+    );
+    assert_eq!(
+        with_align(LineNumberAlign::Right),
+        "warning: unused variable\n   \
+         @ [main.rs]\n \
+         4 │  a\n   \
+         :  ^ here\n\
+         10 │  let y = x;\n   \
+         :      ^ and here\n   \
+         > declared twice\n\
+         \n",
+    );
+}
+
+#[test]
+fn dedent_mode() {
+    let src = "fn main() {\n\t  let x = 1;\n      let y = z;\n}";
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new(src, None))
+        .with_label(Label::new(15..25, "first"))
+        .with_label(Label::new(38..39, "not found"));
+
+    let with_mode = |mode| render(&diagnostic, &Config::plain().with_dedent_mode(mode));
+
+    // a tab and two spaces are as wide as six spaces
+    assert!(with_mode(DedentMode::DisplayWidth).contains(
+        "2 │  let x = 1;\n  \
+         :  ^^^^^^^^^^ first\n\
+         3 │  let y = z;\n  \
+         :        ^ not found\n"
+    ));
+
+    // but they're only three characters
+    assert!(with_mode(DedentMode::CharCount).contains(
+        "2 │  let x = 1;\n  \
+         :  ^^^^^^^^^^ first\n\
+         3 │     let y = z;\n  \
+         :           ^ not found\n"
+    ));
+}
+
+#[test]
+fn localized_line_words() {
+    let diagnostic = Diagnostic::new("erreur: variable inconnue")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "introuvable"));
+
+    let config = Config::default()
+        .with_color(ColorChoice::Never)
+        .with_localization(Localization {
+            line: "ligne".into(),
+            lines: "lignes".into(),
+            ..Default::default()
+        });
+
+    let mut rendered = Vec::new();
+    diagnostic.write_to_compact(&mut rendered, &config).unwrap();
+    let rendered = String::from_utf8(rendered).unwrap();
+    assert!(rendered.contains("[ligne 1]: introuvable"));
+}
+
+#[test]
+fn localized_hidden_labels() {
+    let diagnostic = Diagnostic::new("erreur: variables inconnues")
+        .with_source(Source::new("let x = a + b + c;", None))
+        .with_label(Label::new(8..9, "introuvable"))
+        .with_label(Label::new(12..13, "introuvable"))
+        .with_label(Label::new(16..17, "introuvable"));
+
+    let config = Config::plain()
+        .with_max_labels(Some(1))
+        .with_localization(Localization {
+            and: "et".into(),
+            more_label: "autre étiquette".into(),
+            more_labels: "autres étiquettes".into(),
+            ..Default::default()
+        });
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("  : … et 2 autres étiquettes\n"));
+}
+
+#[test]
+fn east_asian_wide() {
+    let src = "let s = \"±°\" + x;";
+    let diagnostic = Diagnostic::new("error: cannot add")
+        .with_source(Source::new(src, None))
+        .with_label(Label::new(17..18, "not a string"));
+
+    // `±` and `°` take one column each...
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains(
+        "1 │  let s = \"±°\" + x;\n  \
+         :                 ^ not a string\n"
+    ));
+
+    // ...or two each, when they're considered wide
+    let config = Config::plain().with_east_asian_wide(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let s = \"±°\" + x;\n  \
+         :                   ^ not a string\n"
+    ));
+}
+
+#[test]
+fn expand_tabs() {
+    let diagnostic = Diagnostic::new("error: unknown function")
+        .with_source(Source::new("fn main() {\n\tlet x =\tfoo;\n}", None))
+        .with_label(Label::new(21..24, "not found"));
+
+    let config = Config::plain().with_expand_tabs(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "2 │  let x =    foo;\n  \
+         :             ^^^ not found\n"
+    ));
+}
+
+#[test]
+fn carriage_return() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = 1;\rlet y = z;", None))
+        .with_label(Label::new(19..20, "not found"));
+
+    let config = Config::plain().with_carriage_return(CarriageReturn::Strip);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let x = 1;let y = z;\n  \
+         :                    ^ not found\n"
+    ));
+
+    let config = Config::plain().with_carriage_return(CarriageReturn::Escape);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let x = 1;\\rlet y = z;\n  \
+         :                      ^ not found\n"
+    ));
+}
+
+#[test]
+fn strip_ansi() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let a = \x1b[31mred;", None))
+        .with_label(Label::new(13..16, "not found"))
+        .with_label(Label::new(9..11, "inside of an escape sequence"));
+
+    let config = Config::plain()
+        .with_strip_ansi(true)
+        .with_highlight_source(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let a = red;\n  \
+         :          ^^^ not found\n  \
+         :          ^ inside of an escape sequence\n"
+    ));
+}
+
+#[test]
+fn bidi_isolate() {
+    let source = Source::new("let name = \"سلام\";", None);
+    let diagnostic = Diagnostic::error(source, SourceSpan::new(11, 21), "unterminated string");
+
+    let config = Config::default()
+        .with_color(ColorChoice::Never)
+        .with_bidi_isolate(true);
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("\u{2066}let name = \"سلام\";\u{2069}"));
+}
+
+#[test]
+fn dedent() {
+    let src = "impl Foo {\n    fn bar(&self) {\n        self.baz();\n    }\n}";
+    let diagnostic = Diagnostic::new("error: no method named `baz`")
+        .with_source(Source::new(src, None))
+        .with_label(Label::new(44..47, "method not found"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains("3 │  self.baz();\n  :       ^^^ method not found\n"));
+
+    let config = Config::plain().with_dedent(false);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "3 │          self.baz();\n  \
+         :               ^^^ method not found\n"
+    ));
+}
+
+#[test]
+fn caret_on_indented_line() {
+    let src = "fn main() {\n    let x = y;\n        z\n}";
+    let diagnostic = Diagnostic::new("error: unknown variables")
+        .with_source(Source::new(src, None))
+        .with_label(Label::new(24..25, "not found"))
+        .with_label(Label::new(35..36, "not found either"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert_eq!(
+        rendered,
+        "error: unknown variables\n  \
+         @ [unknown]\n\
+         2 │  let x = y;\n  \
+         :          ^ not found\n\
+         3 │      z\n  \
+         :      ^ not found either\n\
+         \n",
+    );
+}
+
+#[test]
+fn dedent_combining_mark() {
+    let src = "    let x;\n   \u{301}y";
+    let diagnostic = Diagnostic::new("error: unexpected accent")
+        .with_source(Source::new(src, None))
+        .with_label(Label::new(4..7, "here"))
+        .with_label(Label::new(src.len() - 1..src.len(), "and here"));
+
+    let config = Config::default().with_color(ColorChoice::Never);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("1 │    let x;\n"));
+    assert!(rendered.contains("2 │   \u{301}y\n  :   ^ and here"));
+}
+
+#[test]
+fn dedent_mixed_indentation() {
+    let diagnostic = Diagnostic::new("error: mixed indentation")
+        .with_source(Source::new("fn main() {\n    let x = 1;\n}", None))
+        .with_label(Label::new(0..2, "function"))
+        .with_label(Label::new(14..16, "indentation"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains("2 │      let x = 1;\n  :   ^^ indentation\n"));
+
+    let diagnostic = Diagnostic::new("error: mixed indentation")
+        .with_source(Source::new("fn main() {\n    let x = 1;\n}", None))
+        .with_label(Label::new(0..2, "function"))
+        .with_label(Label::new(12..19, "indented statement"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains("2 │      let x = 1;\n  : ^^^^^^^^ indented statement\n"));
+}
+
+#[test]
+fn dedent_blank_lines() {
+    let diagnostic = Diagnostic::new("error: empty block")
+        .with_source(Source::new("    {\n\n    }", None))
+        .with_label(Label::new(4..11, "this block"));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.contains("1 │ ┬ {\n"));
+    assert!(rendered.contains("3 │ ┼ }\n"));
+}
+
+#[test]
+fn max_line_width() {
+    let src = "a".repeat(379) + "bad" + &"b".repeat(18);
+    let diagnostic = Diagnostic::new("error: unexpected token")
+        .with_source(Source::new(&src, Some("min.js")))
+        .with_label(Label::new(379..382, "here"));
+
+    let config = Config::plain().with_max_line_width(Some(40));
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  …aaaaaaaaaaaaaaaaaaabadbbbbbbbbbbbbbbbbbb\n  \
+         :                      ^^^ here\n"
+    ));
+}
+
+#[test]
+fn max_line_width_multiline() {
+    let src = "x".repeat(400) + "{\n}";
+    let diagnostic = Diagnostic::new("error: unclosed block")
+        .with_source(Source::new(&src, None))
+        .with_label(Label::new(400..403, "this block"));
+
+    let config = Config::plain().with_max_line_width(Some(10));
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("1 │ ┬ …xxxxxxxxx{\n"));
+}
+
+#[test]
+fn show_position() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new(
+            "fn main() {\n    let x = y;\n}",
+            Some("main.rs"),
+        ))
+        .with_label(Label::new(24..25, "not found"));
+
+    let config = Config::plain().with_show_position(true);
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs:2:13]\n\
+         2 │  let x = y;\n  \
+         :          ^ not found\n\
+         \n",
+    );
+}
+
+#[test]
+fn arrow_header() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new(
+            "fn main() {\n    let x = y;\n}",
+            Some("main.rs"),
+        ))
+        .with_label(Label::new(24..25, "not found"));
+
+    let config = Config::plain()
+        .with_header_style(HeaderStyle::Arrow)
+        .with_show_position(true);
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n \
+         --> main.rs:2:13\n\
+         2 │  let x = y;\n  \
+         :          ^ not found\n\
+         \n",
+    );
+}
+
+#[test]
+fn unknown_source_name() {
+    let diagnostic =
+        Diagnostic::new("error: unexpected end of input").with_source(Source::new("let x =", None));
+
+    let config = Config::default()
+        .with_color(ColorChoice::Never)
+        .with_unknown_source_name("<stdin>");
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("@ [<stdin>]"));
+}
+
+#[test]
+fn message_transform() {
+    fn quote(message: &str) -> Cow<'_, str> {
+        Cow::Owned(format!("'{message}'"))
+    }
+
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", None))
+        .with_label(Label::new(8..9, "not found"))
+        .with_help("declare it first");
+
+    let config = Config::default()
+        .with_color(ColorChoice::Never)
+        .with_message_transform(quote);
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("^ 'not found'"));
+    assert!(rendered.contains("'declare it first'"));
+    assert!(rendered.starts_with("error: unknown variable"));
+}
+
+#[test]
+fn header_gutter_row() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found"));
+
+    assert_eq!(
+        render(&diagnostic, &Config::plain()),
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n\
+         \n",
+    );
+    assert_eq!(
+        render(&diagnostic, &Config::plain().with_header_gutter_row(true)),
+        "error: unknown variable\n  \
+         @ [main.rs]\n  \
+         :\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n\
+         \n",
+    );
+}
+
+#[test]
+fn footer_gutter_row() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found"))
+        .with_help("declare it first");
+
+    let config = Config::plain().with_footer_gutter_row(true);
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n  \
+         :\n  \
+         ? declare it first\n\
+         \n",
+    );
+}
+
+#[test]
+fn max_labels() {
+    let diagnostic = Diagnostic::new("error: unknown variables")
+        .with_source(Source::new("let x = a + b + c + d + e;", None))
+        .with_label(Label::new(8..9, "a"))
+        .with_label(Label::new(12..13, "b"))
+        .with_label(Label::new(16..17, "c"))
+        .with_label(Label::new(20..21, "d"))
+        .with_label(Label::new(24..25, "e"));
+
+    let config = Config::plain().with_max_labels(Some(3));
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variables\n  \
+         @ [unknown]\n\
+         1 │  let x = a + b + c + d + e;\n  \
+         :          ^ a\n  \
+         :              ^ b\n  \
+         :                  ^ c\n  \
+         : … and 2 more labels\n\
+         \n",
+    );
+}
+
+#[test]
+fn trailing_newline() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", Some("main.rs")))
+        .with_label(Label::new(8..9, "not found"));
+
+    let config = Config::default().with_color(ColorChoice::Never);
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n\
+         \n",
+    );
+
+    let config = config.with_trailing_newline(false);
+    let rendered = render(&diagnostic, &config);
+    assert_eq!(
+        rendered,
+        "error: unknown variable\n  \
+         @ [main.rs]\n\
+         1 │  let x = y;\n  \
+         :          ^ not found\n",
+    );
+}
+
+#[test]
+fn clamp_spans() {
+    let diagnostic = Diagnostic::new("error: unterminated string")
+        .with_source(Source::new("let x = \"abc\nlet y = 0;", None))
+        .with_label(Label::new(8..40, "starts here"));
+
+    let mut rendered = String::new();
+    assert!(diagnostic
+        .write_to_fmt(&mut rendered, &Config::plain())
+        .is_err());
+
+    let config = Config::plain().with_clamp_spans(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains("2 │ ┼ let y = 0;"));
+    assert!(rendered.contains("starts here"));
+}
+
+#[test]
+fn min_line_number_width() {
+    let src = "let x = 5;\n".repeat(120);
+    let source = Source::new(&src, Some("main.rs"));
+    let first = Diagnostic::new("error: first")
+        .with_source(source.clone())
+        .with_label(Label::new(4..5, "here"));
+    let last = Diagnostic::new("error: last")
+        .with_source(source)
+        .with_label(Label::new(1313..1314, "here"));
+
+    let config = Config::plain().with_min_line_number_width(Some(3));
+    let rendered = render(&first, &config) + &render(&last, &config);
+
+    assert!(rendered.contains("\n  1 │  let x = 5;\n    :      ^ here\n"));
+    assert!(rendered.contains("\n120 │  let x = 5;\n    :      ^ here\n"));
+}
+
+#[test]
+fn show_ruler() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("fn main() {\n    let x = y;\n}", None))
+        .with_label(Label::new(24..25, "not found"));
+
+    let config = Config::plain().with_show_ruler(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "  :  .5....10..\n\
+         2 │  let x = y;\n  \
+         :          ^ not found\n"
+    ));
+}
+
+#[test]
+fn show_ruler_with_max_line_width() {
+    let src = format!("    {}bad{};", "a".repeat(40), "b".repeat(10));
+    let diagnostic = Diagnostic::new("error: unexpected token")
+        .with_source(Source::new(&src, None))
+        .with_label(Label::new(44..47, "here"));
+
+    let config = Config::plain()
+        .with_show_ruler(true)
+        .with_max_line_width(Some(20));
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "  :   ....40...45...50....\n\
+         1 │  …aaaaaaaabadbbbbbbbbb…\n  \
+         :           ^^^ here\n"
+    ));
+}
+
+#[test]
+fn min_underline_width() {
+    let diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", None))
+        .with_label(Label::new(8..9, "not found"));
+
+    let config = Config::plain().with_min_underline_width(3);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let x = y;\n  \
+         :         ^^^ not found\n"
+    ));
+}
+
+#[test]
+fn min_underline_width_extra_spans() {
+    let diagnostic = Diagnostic::new("error: mismatched operands")
+        .with_source(Source::new("let s = x+y;", None))
+        .with_label(Label::new(8..9, "these").with_extra_span(10..11));
+
+    let config = Config::plain().with_min_underline_width(3);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let s = x+y;\n  \
+         :        ^^^ ^^ these\n"
+    ));
+}
+
+#[test]
+fn multiline_connectors() {
+    let diagnostic = Diagnostic::new("error: unclosed block")
+        .with_source(Source::new("fn main() {\n    let x = 5;", None))
+        .with_label(Label::new(10..25, "this block"));
+
+    let config = Config::plain().with_multiline_connectors(true);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │ ┬ fn main() {\n  \
+         : ├╶╶╶╶╶╶╶╶╶╶╶^\n\
+         2 │ ┼     let x = 5;\n"
+    ));
+}
+
+#[test]
+fn ascii_plain() {
+    let diagnostic = Diagnostic::new("warning: unused variable")
+        .with_source(Source::new("let value = 5;", Some("main.rs")))
+        .with_label(Label::new(4..9, "never read"));
+
+    let rendered = render(&diagnostic, &Config::ascii_plain());
+    assert_eq!(
+        rendered,
+        "warning: unused variable\n  \
+         @ [main.rs]\n\
+         1 |  let value = 5;\n  \
+         :      ^^^^^ never read\n\
+         \n",
+    );
+}
+
+#[test]
+fn min_underline_width_keeps_labels_apart() {
+    let diagnostic = Diagnostic::new("error: unknown variables")
+        .with_source(Source::new("ab", None))
+        .with_label(Label::new(0..1, "a"))
+        .with_label(Label::new(1..2, "b"));
+
+    let config = Config::plain().with_min_underline_width(5);
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  ab\n  \
+         :  ^ a\n  \
+         :   ^ b\n"
+    ));
+
+    let diagnostic = Diagnostic::new("error: unknown variables")
+        .with_source(Source::new("let x = a + b;", None))
+        .with_label(Label::new(8..9, "a"))
+        .with_label(Label::new(12..13, "b"));
+
+    let rendered = render(&diagnostic, &config);
+    assert!(rendered.contains(
+        "1 │  let x = a + b;\n  \
+         :       ^^^^^ a\n  \
+         :             ^^^ b\n"
+    ));
+}
+
+#[test]
+fn footnotes_from_messages() {
+    struct Hint(u32);
+
+    impl std::fmt::Display for Hint {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "hint #{}", self.0)
+        }
+    }
+
+    let owned = String::from("from a &String");
+    let mut diagnostic = Diagnostic::new("error: unknown variable")
+        .with_source(Source::new("let x = y;", None))
+        .with_label(Label::new(8..9, "not found"))
+        .with_footnote(&owned)
+        .with_footnote(format_args!("from {}", "format_args!"))
+        .with_footnote(Cow::Borrowed("from a Cow"));
+    diagnostic.add_footnote(Hint(1));
+
+    let rendered = render(&diagnostic, &Config::plain());
+    assert!(rendered.ends_with(
+        "  > from a &String\n  \
+         > from format_args!\n  \
+         > from a Cow\n  \
+         > hint #1\n\
+         \n"
+    ));
+}